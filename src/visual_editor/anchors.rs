@@ -0,0 +1,140 @@
+use dioxus::prelude::*;
+use super::component::{ComponentType, EditorState, EDITOR_STATE};
+use super::history::push_history;
+use super::symbols::sync_instances;
+
+// Anchor ids are written to the `id` attribute, so whitespace becomes '-' and a leading '#' is dropped;
+// None when nothing is left
+pub fn normalize_anchor(value: &str) -> Option<String> {
+    let anchor = value.trim().trim_start_matches('#').split_whitespace().collect::<Vec<_>>().join("-");
+    if anchor.is_empty() { None } else { Some(anchor) }
+}
+
+// Set (or clear) a component's anchor id as one undoable step
+pub fn set_anchor_id(component_id: usize, value: &str) {
+    let anchor = normalize_anchor(value);
+    if EDITOR_STATE.read().components.get(&component_id).is_none_or(|c| c.anchor_id == anchor) {
+        return;
+    }
+    push_history();
+    let mut state = EDITOR_STATE.write();
+    if let Some(component) = state.components.get_mut(&component_id) {
+        component.anchor_id = anchor;
+        component.touch();
+    }
+}
+
+// `anchor`, or the first of `anchor-2`, `anchor-3`, ... that no component uses yet
+pub fn unique_anchor(state: &EditorState, anchor: &str) -> String {
+    let in_use = |a: &str| state.components.values().any(|c| c.anchor_id.as_deref() == Some(a));
+    if !in_use(anchor) {
+        return anchor.to_string();
+    }
+    (2..).map(|n| format!("{}-{}", anchor, n)).find(|a| !in_use(a)).unwrap_or_default()
+}
+
+// The component that keeps `anchor` on export: the lowest id among the components using it
+pub fn anchor_owner(state: &EditorState, anchor: &str) -> Option<usize> {
+    state.components.values()
+        .filter(|c| c.anchor_id.as_deref() == Some(anchor))
+        .map(|c| c.id)
+        .min()
+}
+
+// Anchor ids set on more than one component, sorted
+pub fn duplicate_anchor_ids(state: &EditorState) -> Vec<String> {
+    let mut anchors = state.components.values().filter_map(|c| c.anchor_id.clone()).collect::<Vec<_>>();
+    anchors.sort();
+    let mut duplicates = anchors.windows(2).filter(|w| w[0] == w[1]).map(|w| w[0].clone()).collect::<Vec<_>>();
+    duplicates.dedup();
+    duplicates
+}
+
+// `id` attribute for a component in the preview and export; only the owner of a duplicated anchor gets it
+pub fn exported_anchor(state: &EditorState, component_id: usize) -> Option<String> {
+    let anchor = state.components.get(&component_id)?.anchor_id.clone()?;
+    (anchor_owner(state, &anchor) == Some(component_id)).then_some(anchor)
+}
+
+// `href` of a Link: its target's anchor, or "#" (top of the page) without a target that has one
+pub fn link_href(state: &EditorState, link_id: usize) -> String {
+    let anchor = state.components.get(&link_id)
+        .and_then(|c| c.link_target)
+        .and_then(|target| state.components.get(&target))
+        .and_then(|target| target.anchor_id.clone());
+    format!("#{}", anchor.unwrap_or_default())
+}
+
+// Point a Link at another component (None: the top of the page) as one undoable step
+fn set_link_target(component_id: usize, target: Option<usize>) {
+    if EDITOR_STATE.read().components.get(&component_id).is_none_or(|c| c.link_target == target) {
+        return;
+    }
+    push_history();
+    let mut state = EDITOR_STATE.write();
+    if let Some(component) = state.components.get_mut(&component_id) {
+        component.link_target = target;
+        component.touch();
+    }
+    sync_instances(&mut state, component_id);
+}
+
+// Anchor id field for the properties panel, plus the target picker for Links
+#[component]
+pub fn AnchorPanel(component_id: usize) -> Element {
+    let state = EDITOR_STATE.read();
+    let Some(component) = state.components.get(&component_id) else { return rsx! {} };
+    let anchor = component.anchor_id.clone().unwrap_or_default();
+    let duplicated_by = component.anchor_id.as_deref()
+        .and_then(|a| anchor_owner(&state, a))
+        .filter(|&owner| owner != component_id);
+    let is_link = component.component_type == ComponentType::Link;
+    let link_target = component.link_target;
+    // a component whose anchor another one owns can't be linked to: the link would land on the owner
+    let target_owner = link_target
+        .and_then(|t| state.components.get(&t))
+        .and_then(|t| t.anchor_id.as_deref())
+        .and_then(|a| anchor_owner(&state, a))
+        .filter(|&owner| Some(owner) != link_target);
+    let mut targets = state.components.values()
+        .filter(|c| c.id != component_id)
+        .filter(|c| c.anchor_id.as_deref().is_some_and(|a| anchor_owner(&state, a) == Some(c.id)))
+        .filter_map(|c| c.anchor_id.clone().map(|a| (c.id, format!("#{} ({} #{})", a, c.component_type.label(), c.id))))
+        .collect::<Vec<_>>();
+    targets.sort();
+
+    rsx! {
+        div { style: "display: flex; flex-direction: column; gap: 6px; padding-inline: 12px; font-size: 12px;",
+            input {
+                r#type: "text",
+                placeholder: "e.g. pricing",
+                aria_label: "Anchor id",
+                value: "{anchor}",
+                onchange: move |e| set_anchor_id(component_id, &e.value()),
+            }
+            if let Some(owner) = duplicated_by {
+                p { style: "margin: 0; color: #e65100;",
+                    "#{owner} already uses this anchor; only #{owner} gets it in the exported page."
+                }
+            }
+            if is_link {
+                label { style: "display: flex; align-items: center; gap: 6px;",
+                    "Links to"
+                    select {
+                        value: "{link_target.map(|t| t.to_string()).unwrap_or_default()}",
+                        onchange: move |e| set_link_target(component_id, e.value().parse().ok()),
+                        option { value: "", "Top of the page" }
+                        for (id, label) in targets {
+                            option { value: "{id}", "{label}" }
+                        }
+                    }
+                }
+                if let Some(owner) = target_owner {
+                    p { style: "margin: 0; color: #e65100;",
+                        "The target shares its anchor with #{owner}, so the link goes to #{owner}."
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,201 @@
+use dioxus::prelude::*;
+use base64::Engine;
+use std::collections::HashMap;
+use std::io::Write;
+use super::component::{ComponentType, EditorState, EDITOR_STATE};
+use super::export::html_for_state;
+
+const BUNDLE_FILE_NAME: &str = "site.zip";
+
+// Media type and decoded bytes of a `data:` URL, base64 or percent-encoded; None for anything else
+// (plain URLs are left alone) and for malformed data
+fn decode_data_url(src: &str) -> Option<(String, Vec<u8>)> {
+    let (header, data) = src.trim().strip_prefix("data:")?.split_once(',')?;
+    let (header, base64) = match header.strip_suffix(";base64") {
+        Some(header) => (header, true),
+        None => (header, false),
+    };
+    let mime = header.split(';').next().unwrap_or_default().trim().to_ascii_lowercase();
+    let bytes = if base64 {
+        let data = data.split_whitespace().collect::<String>();
+        base64::engine::general_purpose::STANDARD.decode(data).ok()?
+    } else {
+        percent_decode(data)?
+    };
+    Some((mime, bytes))
+}
+
+// `%XX` escapes to bytes; None when an escape isn't two hex digits
+fn percent_decode(data: &str) -> Option<Vec<u8>> {
+    let bytes = data.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = std::str::from_utf8(bytes.get(i + 1..i + 3)?).ok()?;
+            out.push(u8::from_str_radix(hex, 16).ok()?);
+            i += 3;
+        } else {
+            out.push(bytes[i]);
+            i += 1;
+        }
+    }
+    Some(out)
+}
+
+fn extension_for(mime: &str) -> &'static str {
+    match mime {
+        "image/png" => "png",
+        "image/jpeg" => "jpg",
+        "image/gif" => "gif",
+        "image/svg+xml" => "svg",
+        "image/webp" => "webp",
+        "image/avif" => "avif",
+        "image/x-icon" | "image/vnd.microsoft.icon" => "ico",
+        _ => "bin",
+    }
+}
+
+// Files of the deployable bundle as (path, bytes), index.html first: the exported page with every data-URL
+// Image moved to assets/ and its src rewritten to the relative path. Images with the same data URL share one file.
+pub fn bundle_files(state: &EditorState) -> Vec<(String, Vec<u8>)> {
+    let mut state = state.clone();
+    let mut ids = state.components.values()
+        .filter(|c| c.component_type == ComponentType::Image)
+        .map(|c| c.id)
+        .collect::<Vec<_>>();
+    ids.sort();
+
+    let mut assets = Vec::new();
+    let mut paths: HashMap<String, String> = HashMap::new();
+    for id in ids {
+        let Some(component) = state.components.get_mut(&id) else { continue };
+        if let Some(path) = paths.get(&component.content) {
+            component.content = path.clone();
+            continue;
+        }
+        let Some((mime, bytes)) = decode_data_url(&component.content) else { continue };
+        let path = format!("assets/image-{}.{}", id, extension_for(&mime));
+        paths.insert(std::mem::replace(&mut component.content, path.clone()), path.clone());
+        assets.push((path, bytes));
+    }
+
+    let mut files = vec![("index.html".to_string(), html_for_state(&state).into_bytes())];
+    files.extend(assets);
+    files
+}
+
+fn zip_bytes(files: &[(String, Vec<u8>)]) -> zip::result::ZipResult<Vec<u8>> {
+    let mut zip = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+    // stored, not deflated: images are compressed already
+    let options = zip::write::SimpleFileOptions::default().compression_method(zip::CompressionMethod::Stored);
+    for (path, bytes) in files {
+        zip.start_file(path.as_str(), options)?;
+        zip.write_all(bytes)?;
+    }
+    Ok(zip.finish()?.into_inner())
+}
+
+// Offer the zip as a browser download
+#[cfg(target_arch = "wasm32")]
+fn save_bundle(bytes: Vec<u8>) -> Result<String, String> {
+    use wasm_bindgen::JsCast;
+
+    let parts = js_sys::Array::of1(&js_sys::Uint8Array::from(bytes.as_slice()));
+    let options = web_sys::BlobPropertyBag::new();
+    options.set_type("application/zip");
+    let url = web_sys::Blob::new_with_u8_array_sequence_and_options(&parts, &options)
+        .and_then(|blob| web_sys::Url::create_object_url_with_blob(&blob))
+        .map_err(|_| "Couldn't create the zip file".to_string())?;
+    let anchor = web_sys::window()
+        .and_then(|w| w.document())
+        .and_then(|d| d.create_element("a").ok())
+        .and_then(|e| e.dyn_into::<web_sys::HtmlAnchorElement>().ok())
+        .ok_or_else(|| "Couldn't start the download".to_string())?;
+    anchor.set_href(&url);
+    anchor.set_download(BUNDLE_FILE_NAME);
+    // the object URL isn't revoked: doing it right after click() can cancel the download in some browsers
+    anchor.click();
+    Ok(format!("Downloaded {}", BUNDLE_FILE_NAME))
+}
+
+// No downloads outside the browser: write the zip to the working directory, next to any earlier
+// bundle rather than over it
+#[cfg(not(target_arch = "wasm32"))]
+fn save_bundle(bytes: Vec<u8>) -> Result<String, String> {
+    let (stem, extension) = BUNDLE_FILE_NAME.rsplit_once('.').unwrap_or((BUNDLE_FILE_NAME, "zip"));
+    let path = std::iter::once(std::path::PathBuf::from(BUNDLE_FILE_NAME))
+        .chain((2..).map(|n| std::path::PathBuf::from(format!("{}-{}.{}", stem, n, extension))))
+        .find(|path| !path.exists())
+        .unwrap_or_default();
+    std::fs::write(&path, bytes).map_err(|e| format!("Couldn't write {}: {}", path.display(), e))?;
+    let path = std::fs::canonicalize(&path).unwrap_or(path);
+    Ok(format!("Saved {}", path.display()))
+}
+
+// Export the page as a zip of index.html plus assets/; returns a message for the export panel
+pub fn export_bundle() -> Result<String, String> {
+    let files = bundle_files(&EDITOR_STATE.read());
+    let assets = files.len() - 1;
+    let bytes = zip_bytes(&files).map_err(|e| format!("Couldn't build the zip: {}", e))?;
+    let message = save_bundle(bytes)?;
+    // exporting the page counts as saving it
+    EDITOR_STATE.write().dirty = false;
+    Ok(format!("{} (index.html and {} image file(s) in assets/)", message, assets))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::visual_editor::component::Component;
+
+    #[test]
+    fn base64_data_urls_are_decoded() {
+        let (mime, bytes) = decode_data_url("data:image/PNG;base64,aGVs bG8=").unwrap();
+        assert_eq!(mime, "image/png");
+        assert_eq!(bytes, b"hello");
+    }
+
+    #[test]
+    fn percent_encoded_data_urls_are_decoded() {
+        let (mime, bytes) = decode_data_url("data:image/svg+xml;charset=utf-8,%3Csvg%2F%3E").unwrap();
+        assert_eq!(mime, "image/svg+xml");
+        assert_eq!(bytes, b"<svg/>");
+    }
+
+    #[test]
+    fn malformed_and_plain_urls_are_left_alone() {
+        assert_eq!(decode_data_url("https://example.com/a.png"), None);
+        assert_eq!(decode_data_url("data:image/png;base64"), None);
+        assert_eq!(decode_data_url("data:image/png;base64,not base64!"), None);
+        assert_eq!(decode_data_url("data:image/svg+xml,%3"), None);
+        assert_eq!(decode_data_url("data:image/svg+xml,%zz"), None);
+    }
+
+    fn image(state: &mut EditorState, src: &str) -> usize {
+        let mut component = Component::new(0, ComponentType::Image, 0.0, 0.0);
+        component.content = src.to_string();
+        state.document_mut().add(component)
+    }
+
+    #[test]
+    fn each_image_gets_its_own_file_and_identical_ones_share_it() {
+        let mut state = EditorState::default();
+        let first = image(&mut state, "data:image/png;base64,aGVsbG8=");
+        image(&mut state, "data:image/png;base64,d29ybGQ=");
+        image(&mut state, "data:image/png;base64,aGVsbG8=");
+        image(&mut state, "https://example.com/a.png");
+
+        let files = bundle_files(&state);
+        let paths = files.iter().map(|(path, _)| path.as_str()).collect::<Vec<_>>();
+        assert_eq!(paths, vec!["index.html", "assets/image-0.png", "assets/image-1.png"]);
+        assert_eq!(files[1].1, b"hello");
+        assert_eq!(files[2].1, b"world");
+
+        let html = String::from_utf8(files[0].1.clone()).unwrap();
+        assert_eq!(html.matches("src=\"assets/image-0.png\"").count(), 2);
+        assert!(html.contains("src=\"https://example.com/a.png\""));
+        // the editor's own document keeps the data URL
+        assert!(state.components[&first].content.starts_with("data:"));
+    }
+}
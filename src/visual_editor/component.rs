@@ -1,18 +1,121 @@
 use dioxus::prelude::*;
-use super::styles_editor::StyleInput;
+use super::styles_editor::{self, pick_color, remember_styles, StyleInput, LAST_STYLES, PAGE_STYLE_ID, STYLE_EDIT_BUFFER};
+use super::export::ExportPanel;
+use super::find_replace::FindReplacePanel;
+use super::history::{can_redo, can_undo, push_history, push_history_once, redo, undo, HistoryTimeline};
+use super::shortcuts::install_keyboard_shortcuts;
+use super::registry::{registered_component_types, renderer_for};
+use super::connections::{can_connect, connect_components, disconnect_components, edge_style, retarget_connection, set_edge_style, EdgeStyle};
+use super::layers::{DeleteConfirmDialog, LayersPanel, PendingDelete};
+use super::layout::tidy_layout;
+use super::routing::{route_arrow, svg_points};
+use super::contrast::ContrastBadge;
+use super::symbols::{sync_instances, SymbolPanel};
+use super::snippets::{duplicate_component, SnippetPanel};
+use super::anchors::{link_href, AnchorPanel};
+use super::gallery::GalleryImport;
+use super::rich_text::{inline_html, plain_text, supports_formatting, FormatToolbar};
+use super::viewport::{center_on, screen_to_canvas, scroll_into_view, Minimap, ViewportControls};
+use super::project::{install_unload_guard, use_change_callback};
+use super::css_reset::{scope_css, CssReset, CssResetPanel};
+use super::toolbox::{set_compact_ui, ToolboxSection, COMPACT_UI};
+use super::type_defaults::{default_styles, TypeDefaultsPanel};
+use super::integrity::state_integrity_check;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::sync::atomic::{AtomicBool, Ordering};
-
-static WINDOW_MOUSEUP_INSTALLED: AtomicBool = AtomicBool::new(false);
-
-#[derive(Clone, Debug, PartialEq)]
+use std::rc::Rc;
+
+// Editor box geometry used for hit-testing and arrow endpoints
+pub const BOX_WIDTH: f64 = 200.0;
+// Widest a box gets when it shows its full content
+pub const EXPANDED_BOX_MAX_WIDTH: f64 = 400.0;
+pub const DEFAULT_BOX_HEIGHT: f64 = 80.0;
+// Allowed canvas grid spacing in canvas pixels
+pub const MIN_GRID_SIZE: f64 = 5.0;
+pub const MAX_GRID_SIZE: f64 = 200.0;
+// How close (in screen pixels) the pointer has to get to a box for a connection being drawn to snap to it
+const CONNECT_SNAP_DISTANCE: f64 = 30.0;
+// Arrow color for connections of the selected components
+const CONNECTION_HIGHLIGHT: &str = "#2196F3";
+// Height new spacers start with, in px
+const DEFAULT_SPACER_HEIGHT: f64 = 24.0;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub enum ComponentType {
     Container,
     Heading,
     Paragraph,
+    Quote,
+    // <form>; holds children like a container
+    Form,
+    // <input>; `content` is the placeholder
+    Input,
+    // <hr>
+    Divider,
+    // empty block whose `height` style sets the gap
+    Spacer,
+    // `content` is HTML inserted as-is (embeds, iframes); never sanitized
+    RawHtml,
+    // <img>; `content` is the source (a URL or a data URL) and the name, if any, is the alt text
+    Image,
+    // <a> to another component's anchor (`link_target`); `content` is the link text
+    Link,
+    // Type registered at runtime through the renderer registry, keyed by name
+    Custom(String),
 }
 
-#[derive(Clone, Debug)]
+impl ComponentType {
+    // Every type but Custom, in the order pickers and summaries list them
+    pub const BUILT_IN: [ComponentType; 11] = [
+        ComponentType::Container, ComponentType::Heading, ComponentType::Paragraph, ComponentType::Quote,
+        ComponentType::Form, ComponentType::Input, ComponentType::Divider, ComponentType::Spacer,
+        ComponentType::RawHtml, ComponentType::Image, ComponentType::Link,
+    ];
+
+    pub fn label(&self) -> &str {
+        match self {
+            ComponentType::Container => "Container",
+            ComponentType::Heading => "Heading",
+            ComponentType::Paragraph => "Paragraph",
+            ComponentType::Quote => "Quote",
+            ComponentType::Form => "Form",
+            ComponentType::Input => "Input",
+            ComponentType::Divider => "Divider",
+            ComponentType::Spacer => "Spacer",
+            ComponentType::RawHtml => "Raw HTML",
+            ComponentType::Image => "Image",
+            ComponentType::Link => "Link",
+            ComponentType::Custom(name) => name,
+        }
+    }
+
+    // Types that can have children
+    pub fn is_container(&self) -> bool {
+        matches!(self, ComponentType::Container | ComponentType::Form)
+    }
+
+    // Background of the type's boxes on the editor canvas (and in the diagram export)
+    pub fn color(&self) -> &'static str {
+        match self {
+            ComponentType::Container => "#4CAF50",
+            ComponentType::Heading => "#2196F3",
+            ComponentType::Paragraph => "#FF9800",
+            ComponentType::Quote => "#9C27B0",
+            ComponentType::Form => "#009688",
+            ComponentType::Input => "#795548",
+            ComponentType::Divider | ComponentType::Spacer => "#9E9E9E",
+            ComponentType::RawHtml => "#E91E63",
+            ComponentType::Image => "#3F51B5",
+            ComponentType::Link => "#00BCD4",
+            ComponentType::Custom(_) => "#607D8B",
+        }
+    }
+}
+
+// `type` attribute choices offered for Input components
+pub const INPUT_TYPES: &[&str] = &["text", "email", "number", "password", "tel", "url", "date", "checkbox"];
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Component {
     pub id: usize,
     pub component_type: ComponentType,
@@ -21,12 +124,137 @@ pub struct Component {
     pub content: String,
     pub x: f64, 
     pub y: f64,
+    // Rendered size of the editor box, measured after every canvas render by measure_component_sizes
+    // (boxes grow with their content). Geometry reads these; nothing else should write them.
+    #[serde(default = "default_box_width")]
+    pub width: f64,
+    pub height: f64,
+    // Milliseconds since the Unix epoch
+    pub created_at: f64,
+    pub updated_at: f64,
+    // Editor-only note for collaborators; saved with the project but never exported to HTML
+    #[serde(default)]
+    pub note: Option<String>,
+    // Editor-only name (e.g. "Hero section"), shown on the canvas box and in the layers panel but not in the
+    // preview/export. Older projects saved it as `label`.
+    #[serde(default, alias = "label")]
+    pub name: Option<String>,
+    // Hidden components stay in the editor but are left out of the preview and exports
+    #[serde(default = "default_true")]
+    pub visible: bool,
+    // Locked components can't be dragged or deleted
+    #[serde(default)]
+    pub locked: bool,
+    // Source of a quote (URL), written to the blockquote's `cite` attribute
+    #[serde(default)]
+    pub cite: Option<String>,
+    // `type` attribute of Input components (None = text)
+    #[serde(default)]
+    pub input_type: Option<String>,
+    // Editor-only: the canvas box grows (up to EXPANDED_BOX_MAX_WIDTH) and wraps to show all of its content
+    // instead of one truncated line
+    #[serde(default)]
+    pub expand_content: bool,
+    // Symbol master this component is an instance of; its content and styles follow the master's
+    #[serde(default)]
+    pub master_id: Option<usize>,
+    // `id` written to the exported element, so Links (and URLs ending in #anchor) can jump to it
+    #[serde(default)]
+    pub anchor_id: Option<String>,
+    // Component whose anchor a Link points at
+    #[serde(default)]
+    pub link_target: Option<usize>,
+    // Relative instead of the default flow positioning: the preview and export shift the element from its
+    // place in the page by its canvas offset from its parent's box (see position_offset)
+    #[serde(default)]
+    pub relative_position: bool,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_box_width() -> f64 {
+    BOX_WIDTH
+}
+
+impl Component {
+    // A fresh, empty component of `component_type` at (x, y) with the default box size, created now
+    pub fn new(id: usize, component_type: ComponentType, x: f64, y: f64) -> Self {
+        let now = now_ms();
+        Component {
+            id,
+            component_type,
+            children: Vec::new(),
+            styles: HashMap::new(),
+            content: String::new(),
+            x,
+            y,
+            width: BOX_WIDTH,
+            height: DEFAULT_BOX_HEIGHT,
+            created_at: now,
+            updated_at: now,
+            note: None,
+            name: None,
+            visible: true,
+            locked: false,
+            cite: None,
+            input_type: None,
+            expand_content: false,
+            master_id: None,
+            anchor_id: None,
+            link_target: None,
+            relative_position: false,
+        }
+    }
+
+    // Mark the component as modified now
+    pub fn touch(&mut self) {
+        self.updated_at = now_ms();
+    }
+}
+
+// Current time in milliseconds since the Unix epoch
+pub fn now_ms() -> f64 {
+    #[cfg(target_arch = "wasm32")]
+    {
+        js_sys::Date::now()
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_millis() as f64)
+            .unwrap_or(0.0)
+    }
+}
+
+// Format a timestamp from now_ms() as "YYYY-MM-DD HH:MM:SS UTC"
+pub fn format_timestamp(ms: f64) -> String {
+    let secs = (ms / 1000.0).floor() as i64;
+    let (days, rem) = (secs.div_euclid(86_400), secs.rem_euclid(86_400));
+
+    // civil-from-days conversion (proleptic Gregorian calendar)
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!("{:04}-{:02}-{:02} {:02}:{:02}:{:02} UTC", year, month, day, rem / 3600, rem % 3600 / 60, rem % 60)
 }
 
 #[derive(Clone, Debug, PartialEq)]
 pub enum EditorMode {
     Editor,
     Preview,
+    // Preview tree with styles replaced by labeled wireframe boxes
+    Outline,
 }
 
 #[derive(Clone, Debug)]
@@ -34,10 +262,19 @@ pub struct EditorState {
     pub components: HashMap<usize, Component>,
     pub next_id: usize,
     pub selected_id: Option<usize>,
+    // All selected components (includes selected_id); more than one enables bulk editing
+    pub selected_ids: Vec<usize>,
     pub dragging_id: Option<usize>,
     pub drag_offset_x: f64,
     pub drag_offset_y: f64,
+    // Position of the dragged component when the drag started
+    pub drag_start_x: f64,
+    pub drag_start_y: f64,
     pub mode: EditorMode,
+    // Styles of the page itself: applied to the preview wrapper and the exported <body>
+    pub page_styles: HashMap<String, String>,
+    // Optional reset stylesheet in front of the page's CSS
+    pub css_reset: CssReset,
     pub hovering_container_id: Option<usize>, // For connection UI
 
     // Connection/drawing state
@@ -45,9 +282,59 @@ pub struct EditorState {
     pub connecting_mouse_x: f64,
     pub connecting_mouse_y: f64,
     pub connecting_hover_target_id: Option<usize>,
+    // Arrow whose head is being dragged to another component; connecting_from is its parent meanwhile
+    pub retargeting_edge: Option<(usize, usize)>,
+
+    // Style row (component id, row in STYLE_EDIT_BUFFER) waiting for a color; the next component clicked supplies it
+    pub eyedropper_active: Option<(usize, usize)>,
 
     // Suppress clicks that occur immediately after a drag
     pub just_dragged: bool,
+
+    // Why the last attempted connection was rejected, until the next connection attempt
+    pub connect_error: Option<String>,
+
+    // Per-connection arrow styles keyed by (parent, child), and the connection selected on the canvas
+    pub edge_styles: HashMap<(usize, usize), EdgeStyle>,
+    pub selected_edge: Option<(usize, usize)>,
+
+    // Toolbox palette drag-and-drop: type being dragged and ghost position (canvas-local)
+    pub palette_drag: Option<ComponentType>,
+    pub palette_ghost: Option<(f64, f64)>,
+
+    // Visual alignment grid drawn behind the canvas boxes
+    pub show_grid: bool,
+    pub grid_size: f64,
+
+    // Draw only the arrows touching a selected component (plus the selected arrow)
+    pub selected_connections_only: bool,
+
+    // Decimal places kept for dragged positions (0 = whole pixels)
+    pub coord_precision: u32,
+
+    // Background colors of the editor canvas and the preview (page styles can still override the latter)
+    pub canvas_background: String,
+    pub preview_background: String,
+
+    // Canvas viewport: screen = canvas * zoom + pan
+    pub zoom: f64,
+    pub pan_x: f64,
+    pub pan_y: f64,
+
+    // The document changed since it was last exported/imported; closing the tab asks for confirmation
+    // unless `warn_on_unload` is off
+    pub dirty: bool,
+    // Bumped with every document change (see mark_changed); on_change watches it instead of the document
+    pub revision: u64,
+    pub warn_on_unload: bool,
+
+    // Ask before deleting with the keyboard (the panels' Delete buttons never ask), and what's
+    // waiting for that answer
+    pub confirm_keyboard_delete: bool,
+    pub pending_delete: Option<PendingDelete>,
+
+    // New components start with the styles last edited on a component of their type (see LAST_STYLES)
+    pub sticky_styles: bool,
 }
 
 impl Default for EditorState {
@@ -56,38 +343,210 @@ impl Default for EditorState {
             components: HashMap::new(),
             next_id: 0,
             selected_id: None,
+            selected_ids: Vec::new(),
             dragging_id: None,
             drag_offset_x: 0.0,
             drag_offset_y: 0.0,
+            drag_start_x: 0.0,
+            drag_start_y: 0.0,
             mode: EditorMode::Editor,
+            page_styles: HashMap::new(),
+            css_reset: CssReset::default(),
             hovering_container_id: None,
 
             connecting_from: None,
             connecting_mouse_x: 0.0,
             connecting_mouse_y: 0.0,
             connecting_hover_target_id: None,
+            retargeting_edge: None,
+
+            eyedropper_active: None,
 
             just_dragged: false,
+
+            connect_error: None,
+
+            edge_styles: HashMap::new(),
+            selected_edge: None,
+
+            palette_drag: None,
+            palette_ghost: None,
+
+            show_grid: false,
+            grid_size: 20.0,
+
+            selected_connections_only: false,
+
+            coord_precision: 0,
+
+            canvas_background: "#f0f0f0".to_string(),
+            preview_background: "#ffffff".to_string(),
+
+            zoom: 1.0,
+            pan_x: 0.0,
+            pan_y: 0.0,
+
+            dirty: false,
+            revision: 0,
+            warn_on_unload: true,
+
+            confirm_keyboard_delete: true,
+            pending_delete: None,
+
+            sticky_styles: false,
         }
     }
 }
 
+impl EditorState {
+    // Record that the document changed: it has unsaved changes and on_change should fire.
+    // Editor-only writes such as measured box sizes, selection or zoom don't call this.
+    pub fn mark_changed(&mut self) {
+        self.dirty = true;
+        self.revision += 1;
+    }
+
+    // Replace the selection with a single component (or clear it)
+    pub fn select(&mut self, id: Option<usize>) {
+        self.selected_id = id;
+        self.selected_ids = id.into_iter().collect();
+        self.selected_edge = None;
+    }
+
+    // Topmost (highest id) component whose editor box contains the canvas-local point and passes `accept`
+    pub fn component_at(&self, x: f64, y: f64, accept: impl Fn(&Component) -> bool) -> Option<usize> {
+        self.components.values()
+            .filter(|c| x >= c.x && x <= c.x + c.width && y >= c.y && y <= c.y + c.height)
+            .filter(|c| accept(c))
+            .map(|c| c.id)
+            .max()
+    }
+
+    // Accepted component whose box is closest to (x, y), if within `max_distance` (0 inside the box)
+    pub fn nearest_component(&self, x: f64, y: f64, max_distance: f64, accept: impl Fn(&Component) -> bool) -> Option<usize> {
+        self.components.values()
+            .filter(|c| accept(c))
+            .map(|c| {
+                let dx = (c.x - x).max(x - (c.x + c.width)).max(0.0);
+                let dy = (c.y - y).max(y - (c.y + c.height)).max(0.0);
+                (dx.hypot(dy), c.id)
+            })
+            .filter(|&(distance, _)| distance <= max_distance)
+            .min_by(|a, b| a.0.total_cmp(&b.0).then(b.1.cmp(&a.1)))
+            .map(|(_, id)| id)
+    }
+
+    // Finish a drag. Only a drag that actually moved the component suppresses the click that follows,
+    // so a plain click (or a mouseup with no drag in progress) never leaves a stale `just_dragged` behind.
+    // Positions are rounded to `coord_precision` on drop so exported coordinates stay clean.
+    pub fn end_drag(&mut self) {
+        let (start_x, start_y) = (self.drag_start_x, self.drag_start_y);
+        let precision = self.coord_precision;
+        let moved = match self.dragging_id.and_then(|id| self.components.get_mut(&id)) {
+            Some(c) => {
+                c.x = round_coord(c.x, precision);
+                c.y = round_coord(c.y, precision);
+                c.x != start_x || c.y != start_y
+            }
+            None => false,
+        };
+        self.dragging_id = None;
+        self.just_dragged = moved;
+    }
+}
+
+// Round a coordinate to `precision` decimal places
+pub fn round_coord(value: f64, precision: u32) -> f64 {
+    let factor = 10f64.powi(precision as i32);
+    (value * factor).round() / factor
+}
+
 pub static EDITOR_STATE: GlobalSignal<EditorState> = Signal::global(EditorState::default);
 
+// Runtime switch for the console diagnostics; only has an effect in builds with the `debug-logging` feature
+pub static DEBUG_LOGGING: GlobalSignal<bool> = Signal::global(|| false);
+
+// Log to the browser console when diagnostics are compiled in and switched on.
+// The message is built lazily so disabled logging costs nothing in hot handlers like mousemove.
+pub fn debug_log(message: impl FnOnce() -> String) {
+    #[cfg(all(target_arch = "wasm32", feature = "debug-logging"))]
+    {
+        if *DEBUG_LOGGING.peek() {
+            web_sys::console::log_1(&message().into());
+        }
+    }
+
+    #[cfg(not(all(target_arch = "wasm32", feature = "debug-logging")))]
+    {
+        let _ = message;
+    }
+}
+
+// e.g. "12 components (3 containers, 5 headings, 4 paragraphs)"; types with no components are left out
+pub fn component_summary(state: &EditorState) -> String {
+    fn plural(count: usize, word: &str) -> String {
+        format!("{} {}{}", count, word, if count == 1 { "" } else { "s" })
+    }
+
+    let mut types = ComponentType::BUILT_IN.to_vec();
+    let mut custom = state.components.values()
+        .filter_map(|c| match &c.component_type {
+            ComponentType::Custom(name) => Some(ComponentType::Custom(name.clone())),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    custom.sort_by(|a, b| a.label().cmp(b.label()));
+    custom.dedup();
+    types.extend(custom);
+
+    let breakdown = types.iter()
+        .map(|t| (t, state.components.values().filter(|c| &c.component_type == t).count()))
+        .filter(|&(_, count)| count > 0)
+        .map(|(t, count)| plural(count, &t.label().to_lowercase()))
+        .collect::<Vec<_>>();
+    let total = plural(state.components.len(), "component");
+    if breakdown.is_empty() {
+        total
+    } else {
+        format!("{} ({})", total, breakdown.join(", "))
+    }
+}
+
+// The editor. Apps embedding it can pass `on_change` to receive the project JSON (as saved by
+// export_state_json) a moment after each document change; see use_change_callback for what counts.
 #[component]
-pub fn VisualEditor() -> Element {
+pub fn VisualEditor(on_change: Option<EventHandler<String>>) -> Element {
+    // held for as long as the editor is mounted; dropping them on unmount detaches the listeners
+    use_hook(|| Rc::new(install_keyboard_shortcuts()));
+    use_hook(|| Rc::new(install_unload_guard()));
+    // releasing the mouse outside the canvas also stops dragging
+    use_hook(|| Rc::new(WindowListener::new("mouseup", |_| stop_dragging())));
+    use_change_callback(on_change);
     let state = EDITOR_STATE.read();
     let editor_bg = if state.mode == EditorMode::Editor { "var(--color-primary)" } else { "var(--color-secondary)" };
     let preview_bg = if state.mode == EditorMode::Preview { "var(--color-primary)" } else { "var(--color-secondary)" };
+    let outline_bg = if state.mode == EditorMode::Outline { "var(--color-primary)" } else { "var(--color-secondary)" };
     
     rsx! {
         div {
-            class: "visual-editor",
+            class: if COMPACT_UI() { "visual-editor density-compact" } else { "visual-editor" },
             style: "display: flex; height: 100vh; font-family: system-ui;",
+
+            DeleteConfirmDialog {}
             
             div {
                 class: "toolbox",
-                h2 { style: "margin: 0 0 16px 0; font-size: 18px;", "Components" }
+                h2 { style: "margin: 0 0 4px 0; font-size: 18px;", "Toolbox" }
+                p { style: "margin: 0 0 16px 0; font-size: 12px; color: #666;", "{component_summary(&state)}" }
+                label { style: "font-size: 12px; display: flex; align-items: center; gap: 6px; margin-bottom: 16px;",
+                    "Density"
+                    select {
+                        value: if COMPACT_UI() { "compact" } else { "comfortable" },
+                        onchange: move |e| set_compact_ui(e.value() == "compact"),
+                        option { value: "comfortable", "Comfortable" }
+                        option { value: "compact", "Compact" }
+                    }
+                }
                 
                 div {
                     class: "mode-toggle",
@@ -102,52 +561,214 @@ pub fn VisualEditor() -> Element {
                         style: "background: {preview_bg};",
                         "Preview"
                     }
+                    button {
+                        onclick: move |_| set_mode(EditorMode::Outline),
+                        style: "background: {outline_bg};",
+                        "Outline"
+                    }
                 }
                 
                 if state.mode == EditorMode::Editor {
                     div {
-                        class: "component-buttons",
-                        style: "display: flex; flex-direction: column; gap: 8px;",
-                        
+                        style: "margin-bottom: 16px; display: flex; gap: 8px;",
                         button {
-                            onclick: move |_| add_component(ComponentType::Container),
-                            "Container"
+                            disabled: !can_undo(),
+                            onclick: move |_| undo(),
+                            "Undo"
                         }
                         button {
-                            onclick: move |_| add_component(ComponentType::Heading),
-                            "Heading"
+                            disabled: !can_redo(),
+                            onclick: move |_| redo(),
+                            "Redo"
                         }
-                        button {
-                            onclick: move |_| add_component(ComponentType::Paragraph),
-                            "Paragraph"
+                    }
+                    HistoryTimeline {}
+
+                    ToolboxSection { title: "Components",
+                        div {
+                            class: "component-buttons",
+                            style: "display: flex; flex-direction: column; gap: 8px;",
+
+                            PaletteButton { component_type: ComponentType::Container }
+                            PaletteButton { component_type: ComponentType::Heading }
+                            PaletteButton { component_type: ComponentType::Paragraph }
+                            PaletteButton { component_type: ComponentType::Quote }
+                            PaletteButton { component_type: ComponentType::Form }
+                            PaletteButton { component_type: ComponentType::Input }
+                            PaletteButton { component_type: ComponentType::Divider }
+                            PaletteButton { component_type: ComponentType::Spacer }
+                            PaletteButton { component_type: ComponentType::RawHtml }
+                            PaletteButton { component_type: ComponentType::Image }
+                            PaletteButton { component_type: ComponentType::Link }
+                            for name in registered_component_types() {
+                                PaletteButton { component_type: ComponentType::Custom(name) }
+                            }
+                        }
+                        label {
+                            style: "font-size: 12px; display: flex; align-items: center; gap: 6px; margin-top: 8px;",
+                            title: "New components copy the styles of the last component of the same type you styled",
+                            input {
+                                r#type: "checkbox",
+                                checked: state.sticky_styles,
+                                onchange: move |e| EDITOR_STATE.write().sticky_styles = e.checked(),
+                            }
+                            "Reuse last styles"
                         }
+                        GalleryImport {}
                     }
-                    
-                    div { style: "margin-top: 24px;",
-                        h3 { style: "margin: 0 0 8px 0; font-size: 14px;", "Instructions" }
-                        p { style: "font-size: 12px; color: #666; line-height: 1.4;",
+
+                    ToolboxSection { title: "Instructions",
+                        p { style: "font-size: 12px; color: #666; line-height: 1.4; margin: 0;",
                             "Click boxes to select"
                             br {}
-                            "Drag boxes to move"
+                            "Drag boxes to move (hold Shift to lock an axis)"
+                            br {}
+                            "Drag toolbox items onto the canvas (or a container) to place them"
                             br {}
-                            " Containers can have children"
+                            " Containers and forms can have children"
                             br {}
                             " Connect with arrows"
+                            br {}
+                            " Ctrl+E / Ctrl+P switch Editor / Preview"
+                            br {}
+                            " F centers the selected component"
+                            br {}
+                            " Esc selects the parent container"
+                            br {}
+                            " Delete removes the selected connection or components"
+                            br {}
+                            " Ctrl+C / Ctrl+X / Ctrl+V copy, cut and paste (into the selected container)"
+                            br {}
+                            " Ctrl+D duplicates the selected component"
+                            br {}
+                            " Tab / Shift+Tab select the next / previous component (with the canvas focused)"
+                            br {}
+                            " Alt+↑ / Alt+↓ reorder within the container"
+                            br {}
+                            " Ctrl+↑ / Ctrl+↓ move out of / into a container"
+                        }
+                    }
+
+                    ToolboxSection { title: "Canvas",
+                        label { style: "font-size: 12px; display: flex; align-items: center; gap: 6px;",
+                            input {
+                                r#type: "checkbox",
+                                checked: state.show_grid,
+                                onchange: move |e| EDITOR_STATE.write().show_grid = e.checked(),
+                            }
+                            "Show grid"
+                        }
+                        label { style: "font-size: 12px; display: flex; align-items: center; gap: 6px; margin-top: 8px;",
+                            "Grid spacing"
+                            input {
+                                r#type: "number",
+                                style: "width: 60px;",
+                                min: "{MIN_GRID_SIZE}",
+                                max: "{MAX_GRID_SIZE}",
+                                value: "{state.grid_size}",
+                                onchange: move |e| set_grid_size(&e.value()),
+                            }
+                            "px"
+                        }
+                        label { style: "font-size: 12px; display: flex; align-items: center; gap: 6px; margin-top: 8px;",
+                            "Show connections"
+                            select {
+                                value: if state.selected_connections_only { "selected" } else { "all" },
+                                onchange: move |e| EDITOR_STATE.write().selected_connections_only = e.value() == "selected",
+                                option { value: "all", "All" }
+                                option { value: "selected", "Selected only" }
+                            }
+                        }
+                        label { style: "font-size: 12px; display: flex; align-items: center; gap: 6px; margin-top: 8px;",
+                            "Position precision"
+                            select {
+                                value: "{state.coord_precision}",
+                                onchange: move |e| EDITOR_STATE.write().coord_precision = e.value().parse().unwrap_or(0),
+                                option { value: "0", "1px" }
+                                option { value: "1", "0.1px" }
+                                option { value: "2", "0.01px" }
+                            }
+                        }
+                        label { style: "font-size: 12px; display: flex; align-items: center; gap: 6px; margin-top: 8px;",
+                            input {
+                                r#type: "color",
+                                value: "{state.canvas_background}",
+                                oninput: move |e| EDITOR_STATE.write().canvas_background = e.value(),
+                            }
+                            "Canvas background"
+                        }
+                        label { style: "font-size: 12px; display: flex; align-items: center; gap: 6px; margin-top: 8px;",
+                            input {
+                                r#type: "color",
+                                value: "{state.preview_background}",
+                                oninput: move |e| EDITOR_STATE.write().preview_background = e.value(),
+                            }
+                            "Preview background"
+                        }
+                        ViewportControls {}
+                        button {
+                            style: "margin-top: 8px;",
+                            title: "Arrange components as a tree: parents above children, root trees in a row",
+                            onclick: move |_| tidy_layout(),
+                            "Tidy layout"
+                        }
+                        label { style: "font-size: 12px; display: flex; align-items: center; gap: 6px; margin-top: 8px;",
+                            input {
+                                r#type: "checkbox",
+                                checked: state.confirm_keyboard_delete,
+                                onchange: move |e| EDITOR_STATE.write().confirm_keyboard_delete = e.checked(),
+                            }
+                            "Confirm Delete key"
+                        }
+                        // the switch only exists in builds that compile the diagnostics in
+                        if cfg!(feature = "debug-logging") {
+                            label { style: "font-size: 12px; display: flex; align-items: center; gap: 6px; margin-top: 8px;",
+                                input {
+                                    r#type: "checkbox",
+                                    checked: DEBUG_LOGGING(),
+                                    onchange: move |e| *DEBUG_LOGGING.write() = e.checked(),
+                                }
+                                "Log diagnostics to the console"
+                            }
                         }
                     }
+
+                    ToolboxSection { title: "Page settings",
+                        StyleInput { component_id: PAGE_STYLE_ID }
+                        CssResetPanel {}
+                    }
+                    ToolboxSection { title: "Type defaults",
+                        TypeDefaultsPanel {}
+                    }
+
+                    ToolboxSection { title: "Layers",
+                        LayersPanel {}
+                    }
+                    ToolboxSection { title: "Snippets",
+                        SnippetPanel {}
+                    }
+                    ToolboxSection { title: "Find & replace",
+                        FindReplacePanel {}
+                    }
+                    ToolboxSection { title: "Export",
+                        ExportPanel {}
+                    }
                 }
             }
             
             // Center - Canvas
             div {
-                id: "canvas",
+                id: CANVAS_ID,
                 class: "canvas-wrapper",
-                style: "flex: 1; background: #f0f0f0; overflow: hidden; position: relative;",
+                // focusable so Tab cycles the selection while the canvas has focus (and only then)
+                tabindex: "0",
+                aria_label: "Canvas",
+                style: "flex: 1; background: {state.canvas_background}; overflow: hidden; position: relative;",
                 
                 if state.mode == EditorMode::Editor {
                     Canvas {}
                 } else {
-                    PreviewCanvas {}
+                    PreviewCanvas { outline: state.mode == EditorMode::Outline }
                 }
             }
             
@@ -155,27 +776,98 @@ pub fn VisualEditor() -> Element {
             if state.mode == EditorMode::Editor {
                 div {
                     class: "properties",
-                    PropertiesPanel {}
+                    // a one-item keyed list: a new selection remounts the whole panel, so no input keeps the
+                    // previous component's value (Dioxus only compares keys between list items)
+                    for selected in [state.selected_id] {
+                        PropertiesPanel { key: "{selected:?}" }
+                    }
                 }
             }
         }
     }
 }
 
+// Toolbox entry: click adds at the default offset, dragging onto the canvas drops it at the cursor
+#[component]
+fn PaletteButton(component_type: ComponentType) -> Element {
+    let label = component_type.label().to_string();
+    let drag_type = component_type.clone();
+    let icon_type = component_type.clone();
+
+    rsx! {
+        button {
+            draggable: "true",
+            onclick: move |_| { add_component(component_type.clone()); },
+            ondragstart: move |_| EDITOR_STATE.write().palette_drag = Some(drag_type.clone()),
+            ondragend: move |_| end_palette_drag(),
+            style: "display: flex; align-items: center; gap: 6px;",
+            TypeIcon { component_type: icon_type }
+            "{label}"
+        }
+    }
+}
+
+// Small line icon per component type (drawn in the current text color)
+#[component]
+fn TypeIcon(component_type: ComponentType) -> Element {
+    let path = match component_type {
+        // nested boxes
+        ComponentType::Container => "M3 3h18v18H3z M7 7h10v10H7z",
+        // an "H"
+        ComponentType::Heading => "M6 4v16 M18 4v16 M6 12h12",
+        // lines of text
+        ComponentType::Paragraph => "M4 6h16 M4 11h16 M4 16h10",
+        // a pair of quote marks
+        ComponentType::Quote => "M5 7h5v5l-3 5 M14 7h5v5l-3 5",
+        // a box with form fields
+        ComponentType::Form => "M3 3h18v18H3z M6 8h12 M6 13h12 M6 18h6",
+        // a text field with a caret
+        ComponentType::Input => "M3 8h18v8H3z M7 10v4",
+        // a horizontal rule
+        ComponentType::Divider => "M3 12h18",
+        // a gap between two arrows
+        ComponentType::Spacer => "M4 4h16 M4 20h16 M12 7v10 M9 10l3-3 3 3 M9 14l3 3 3-3",
+        // angle brackets
+        ComponentType::RawHtml => "M8 6l-6 6 6 6 M16 6l6 6-6 6",
+        // a framed landscape
+        ComponentType::Image => "M3 5h18v14H3z M3 16l5-5 4 4 3-3 6 6",
+        // two chain links
+        ComponentType::Link => "M10 14a4 4 0 0 0 6 0l3-3a4 4 0 0 0-6-6l-1 1 M14 10a4 4 0 0 0-6 0l-3 3a4 4 0 0 0 6 6l1-1",
+        // a diamond for anything registered at runtime
+        ComponentType::Custom(_) => "M12 3l9 9-9 9-9-9z",
+    };
+
+    rsx! {
+        svg {
+            width: "14",
+            height: "14",
+            view_box: "0 0 24 24",
+            fill: "none",
+            stroke: "currentColor",
+            stroke_width: "2",
+            stroke_linecap: "round",
+            style: "flex-shrink: 0; vertical-align: -2px;",
+            path { d: path }
+        }
+    }
+}
+
 #[component]
 fn Canvas() -> Element {
+    // re-measure all boxes after each render so arrows, hit-testing and framing use real sizes
+    use_effect(measure_component_sizes);
     let state = EDITOR_STATE.read();
 
     // Compute preview line coordinates outside of rsx! to avoid complex let bindings inside the macro
     let preview_line_coords = if let Some(from_id) = state.connecting_from {
         if let Some(from_comp) = state.components.get(&from_id) {
-            let start_cx = from_comp.x + 100.0;
-            let start_cy = from_comp.y + 40.0;
+            let start_cx = from_comp.x + from_comp.width / 2.0;
+            let start_cy = from_comp.y + from_comp.height / 2.0;
 
             // end point snaps to target edge when hovering a valid component, otherwise follows mouse
             let (end_x, end_y) = if let Some(target_id) = state.connecting_hover_target_id {
                 if let Some(target) = state.components.get(&target_id) {
-                    rect_edge_point_towards(start_cx, start_cy, target.x, target.y, 200.0, 80.0)
+                    rect_edge_point_towards(start_cx, start_cy, target.x, target.y, target.width, target.height)
                 } else {
                     (state.connecting_mouse_x, state.connecting_mouse_y)
                 }
@@ -184,7 +876,7 @@ fn Canvas() -> Element {
             };
 
             // start point should snap to parent edge towards the end point
-            let (sx, sy) = rect_edge_point_towards(end_x, end_y, from_comp.x, from_comp.y, 200.0, 80.0);
+            let (sx, sy) = rect_edge_point_towards(end_x, end_y, from_comp.x, from_comp.y, from_comp.width, from_comp.height);
             Some((sx, sy, end_x, end_y))
         } else {
             None
@@ -193,88 +885,199 @@ fn Canvas() -> Element {
         None
     };
 
+    // the grid is drawn on the untransformed canvas, so scale and offset it to follow the viewport
+    let grid_style = if state.show_grid {
+        format!(
+            "background-image: radial-gradient(circle, #bbb 1px, transparent 1px); background-size: {size}px {size}px; background-position: {px}px {py}px;",
+            size = state.grid_size * state.zoom,
+            px = state.pan_x,
+            py = state.pan_y,
+        )
+    } else {
+        String::new()
+    };
+    let mut arrow_colors = state.edge_styles.values().map(|s| s.color.clone()).collect::<Vec<_>>();
+    arrow_colors.push(EdgeStyle::default().color);
+    arrow_colors.push(CONNECTION_HIGHLIGHT.to_string());
+    arrow_colors.sort();
+    arrow_colors.dedup();
+    let viewport_transform = format!("translate({}px, {}px) scale({})", state.pan_x, state.pan_y, state.zoom);
+    let shows_edge = |parent: usize, child: usize| {
+        !state.selected_connections_only
+            || state.selected_ids.contains(&parent)
+            || state.selected_ids.contains(&child)
+            || state.selected_edge == Some((parent, child))
+    };
+
     rsx! {
         div {
             class: "canvas",
-            style: "width: 100%; height: 100%; position: relative;",
-            // Cancel connecting on background click
+            style: "width: 100%; height: 100%; position: relative; overflow: hidden; {grid_style}",
+            // Background click while connecting: finish on the target the line snapped to, otherwise cancel
             onmousedown: move |_| {
-                if EDITOR_STATE.read().connecting_from.is_some() {
+                let (from, target) = { let s = EDITOR_STATE.read(); (s.connecting_from, s.connecting_hover_target_id) };
+                if let Some(from) = from {
+                    if let Some(target) = target {
+                        complete_connection(from, target);
+                    }
+                    stop_connecting();
+                }
+            },
+            onmouseup: move |_| {
+                stop_dragging();
+                // an arrowhead released over empty canvas goes to the target it snapped to, if any
+                let (retargeting, target) = { let s = EDITOR_STATE.read(); (s.retargeting_edge, s.connecting_hover_target_id) };
+                if let Some((from, _)) = retargeting {
+                    if let Some(target) = target {
+                        complete_connection(from, target);
+                    }
                     stop_connecting();
                 }
             },
-            onmouseup: move |_| stop_dragging(),
             // update dragging & connecting preview
-            onmousemove: move |e| handle_mouse_move(e.page_coordinates().x, e.page_coordinates().y),
-
-            // Draw connection arrows
-            svg {
-                style: "position: absolute; top: 0; left: 0; width: 100%; height: 100%; pointer-events: none;",
-                for (id, component) in state.components.iter() {
-                    for child_id in component.children.iter() {
-                        if let Some(child) = state.components.get(child_id) {
-                            {
-                                // Compute snapped endpoints so arrows touch the child edge (and parent edge)
-                                let parent_cx = component.x + 100.0;
-                                let parent_cy = component.y + 40.0;
-
-                                let (x1, y1) = rect_edge_point_towards(child.x + 100.0, child.y + 40.0, component.x, component.y, 200.0, 80.0); // parent edge
-                                let (x2, y2) = rect_edge_point_towards(parent_cx, parent_cy, child.x, child.y, 200.0, 80.0); // child edge
-
-                                rsx! {
-                                    line {
-                                        x1: "{x1}",
-                                        y1: "{y1}",
-                                        x2: "{x2}",
-                                        y2: "{y2}",
-                                        stroke: "#666",
-                                        stroke_width: "2",
-                                        marker_end: "url(#arrowhead)",
+            onmousemove: move |e| handle_mouse_move(e.page_coordinates().x, e.page_coordinates().y, e.modifiers().shift()),
+            // toolbox palette drops; preventing dragover's default is what allows the drop
+            ondragover: move |e| {
+                if EDITOR_STATE.read().palette_drag.is_some() {
+                    e.prevent_default();
+                    let local = page_to_local(e.page_coordinates().x, e.page_coordinates().y);
+                    EDITOR_STATE.write().palette_ghost = Some(local);
+                }
+            },
+            ondrop: move |e| {
+                e.prevent_default();
+                drop_palette_component(e.page_coordinates().x, e.page_coordinates().y);
+            },
+
+            if let Some(msg) = state.connect_error.clone() {
+                div {
+                    style: "position: absolute; top: 8px; left: 8px; z-index: 10; padding: 6px 10px; font-size: 12px;
+                            background: #fff3e0; border: 1px solid #FF5722; border-radius: 4px; cursor: pointer;",
+                    title: "Click to dismiss",
+                    onmousedown: move |e| e.stop_propagation(),
+                    onclick: move |_| EDITOR_STATE.write().connect_error = None,
+                    "{msg}"
+                }
+            }
+
+            Minimap {}
+
+            // Everything below is positioned in canvas coordinates and moved/scaled by the viewport
+            div {
+                style: "position: absolute; top: 0; left: 0; width: 100%; height: 100%; transform-origin: 0 0; transform: {viewport_transform};",
+
+                // Draw connection arrows
+                svg {
+                    style: "position: absolute; top: 0; left: 0; width: 100%; height: 100%; overflow: visible; pointer-events: none;",
+                    for (id, component) in state.components.iter() {
+                        for child_id in component.children.iter().filter(|&&child_id| shows_edge(*id, child_id)) {
+                            if let Some(child) = state.components.get(child_id) {
+                                {
+                                    // Compute snapped endpoints so arrows touch the child edge (and parent edge)
+                                    let parent_cx = component.x + component.width / 2.0;
+                                    let parent_cy = component.y + component.height / 2.0;
+
+                                    let (x1, y1) = rect_edge_point_towards(child.x + child.width / 2.0, child.y + child.height / 2.0, component.x, component.y, component.width, component.height); // parent edge
+                                    let (x2, y2) = rect_edge_point_towards(parent_cx, parent_cy, child.x, child.y, child.width, child.height); // child edge
+                                    // bends around boxes sitting on the straight line
+                                    let points = svg_points(&route_arrow(&state, *id, *child_id, (x1, y1), (x2, y2)));
+
+                                    let edge = (*id, *child_id);
+                                    let style = edge_style(&state, edge.0, edge.1);
+                                    let is_selected = state.selected_edge == Some(edge);
+                                    // arrows of the selected boxes stand out; a selected arrow keeps its own color
+                                    let touches_selection = state.selected_ids.contains(id) || state.selected_ids.contains(child_id);
+                                    let stroke_width = if is_selected { "4" } else if touches_selection { "3" } else { "2" };
+                                    let color = if touches_selection && !is_selected { CONNECTION_HIGHLIGHT } else { style.color.as_str() };
+                                    let dash = if style.dashed { "8 5" } else { "none" };
+                                    let marker = arrowhead_id(color);
+
+                                    rsx! {
+                                        polyline {
+                                            points: "{points}",
+                                            fill: "none",
+                                            stroke: "{color}",
+                                            stroke_width: "{stroke_width}",
+                                            stroke_dasharray: "{dash}",
+                                            marker_end: "url(#{marker})",
+                                        }
+                                        // wider invisible line so the arrow is easy to click
+                                        polyline {
+                                            points: "{points}",
+                                            fill: "none",
+                                            stroke: "transparent",
+                                            stroke_width: "12",
+                                            style: "pointer-events: stroke; cursor: pointer;",
+                                            onmousedown: move |e| e.stop_propagation(),
+                                            onclick: move |e| { e.stop_propagation(); select_edge(edge); },
+                                        }
+                                        // grab the arrowhead to drag the connection onto another component
+                                        circle {
+                                            cx: "{x2}",
+                                            cy: "{y2}",
+                                            r: "8",
+                                            fill: "transparent",
+                                            style: "pointer-events: all; cursor: crosshair;",
+                                            onmousedown: move |e| { e.stop_propagation(); start_retargeting(edge); },
+                                        }
                                     }
                                 }
                             }
                         }
                     }
-                }
 
-                // Arrow marker definition
-                defs {
-                    marker {
-                        id: "arrowhead",
-                        marker_width: "10",
-                        marker_height: "10",
-                        ref_x: "9",
-                        ref_y: "3",
-                        orient: "auto",
-                        polygon {
-                            points: "0 0, 10 3, 0 6",
-                            fill: "#666"
+                    // Arrow marker definitions, one per arrow color in use
+                    defs {
+                        for color in arrow_colors {
+                            marker {
+                                id: arrowhead_id(&color),
+                                marker_width: "10",
+                                marker_height: "10",
+                                ref_x: "9",
+                                ref_y: "3",
+                                orient: "auto",
+                                polygon {
+                                    points: "0 0, 10 3, 0 6",
+                                    fill: "{color}"
+                                }
+                            }
                         }
                     }
-                }
 
-                // Preview connecting line (while the user is drawing a new connection)
-                if let Some((sx, sy, end_x, end_y)) = preview_line_coords {
-                    {
-                        rsx! {
-                            line {
-                                x1: "{sx}",
-                                y1: "{sy}",
-                                x2: "{end_x}",
-                                y2: "{end_y}",
-                                stroke: "#f44336",
-                                stroke_width: "2",
-                                stroke_dasharray: "6 4",
-                                marker_end: "url(#arrowhead)",
+                    // Preview connecting line (while the user is drawing a new connection)
+                    if let Some((sx, sy, end_x, end_y)) = preview_line_coords {
+                        {
+                            rsx! {
+                                line {
+                                    x1: "{sx}",
+                                    y1: "{sy}",
+                                    x2: "{end_x}",
+                                    y2: "{end_y}",
+                                    stroke: "#f44336",
+                                    stroke_width: "2",
+                                    stroke_dasharray: "6 4",
+                                    marker_end: "url(#arrowhead)",
+                                }
                             }
                         }
                     }
                 }
-            }
 
-            // Draw component boxes
-            for (id, component) in state.components.iter() {
-                ComponentBox { component_id: *id }
+                // Draw component boxes
+                for id in state.components.keys() {
+                    ComponentBox { component_id: *id }
+                }
+
+                // Ghost of the toolbox item being dragged in
+                if let (Some(ty), Some((gx, gy))) = (state.palette_drag.as_ref(), state.palette_ghost) {
+                    div {
+                        style: "position: absolute; left: {gx - BOX_WIDTH / 2.0}px; top: {gy - DEFAULT_BOX_HEIGHT / 2.0}px;
+                                width: {BOX_WIDTH}px; height: {DEFAULT_BOX_HEIGHT}px; box-sizing: border-box;
+                                border: 2px dashed #333; border-radius: 8px; opacity: 0.5; pointer-events: none;
+                                padding: 12px; font-size: 14px; font-weight: bold; color: #333;",
+                        "{ty.label()}"
+                    }
+                }
             }
         }
     }
@@ -284,28 +1087,55 @@ fn Canvas() -> Element {
 fn ComponentBox(component_id: usize) -> Element {
     let state = EDITOR_STATE.read();
     let (component_type, component_content, component_children_len, component_x, component_y) = if let Some(c) = state.components.get(&component_id) {
-        (c.component_type.clone(), &c.content, c.children.len(), c.x, c.y)
+        // custom types decide what their box shows through the registry
+        let content = match &c.component_type {
+            ComponentType::Custom(name) => renderer_for(name).editor_summary(c),
+            t if supports_formatting(t) => plain_text(&c.content),
+            // the source is drawn as a thumbnail, not printed (data URLs are huge)
+            ComponentType::Image => String::new(),
+            _ => c.content.clone(),
+        };
+        (c.component_type.clone(), content, c.children.len(), c.x, c.y)
     } else {
         panic!("Not found")
     };
-    let is_selected = state.selected_id == Some(component_id);
+    let component_rotation = state.components.get(&component_id).map(|c| rotation_deg(&c.styles)).unwrap_or(0.0);
+    let component_note = state.components.get(&component_id).and_then(|c| c.note.clone());
+    let component_name = state.components.get(&component_id).and_then(|c| c.name.clone());
+    let (is_visible, is_locked) = state.components.get(&component_id).map(|c| (c.visible, c.locked)).unwrap_or((true, false));
+    let master_id = state.components.get(&component_id).and_then(|c| c.master_id);
+    let expand_content = state.components.get(&component_id).is_some_and(|c| c.expand_content);
+    let image_src = state.components.get(&component_id)
+        .filter(|c| c.component_type == ComponentType::Image && !c.content.is_empty())
+        .map(|c| c.content.clone());
+    let box_width = if expand_content {
+        format!("width: max-content; min-width: {}px; max-width: {}px;", BOX_WIDTH, EXPANDED_BOX_MAX_WIDTH)
+    } else {
+        format!("width: {}px;", BOX_WIDTH)
+    };
+    let content_wrap = if expand_content {
+        "white-space: pre-wrap; overflow-wrap: anywhere;"
+    } else {
+        "overflow: hidden; text-overflow: ellipsis; white-space: nowrap;"
+    };
+    // hidden components stay editable but fade out on the canvas
+    let box_opacity = if is_visible { 1.0 } else { 0.35 };
+    let cursor = if state.eyedropper_active.is_some() { "crosshair" } else if is_locked { "default" } else { "grab" };
+    let is_selected = state.selected_ids.contains(&component_id);
     let is_hovering = state.hovering_container_id == Some(component_id);
     let is_connect_target = state.connecting_hover_target_id == Some(component_id);
 
     // Precompute whether this is the container that is currently initiating a connection
     let is_connecting_from_here = state.connecting_from == Some(component_id);
 
-    let (type_name, type_color) = match component_type {
-        ComponentType::Container => ("Container", "#4CAF50"),
-        ComponentType::Heading => ("Heading", "#2196F3"),
-        ComponentType::Paragraph => ("Paragraph", "#FF9800"),
-    };
+    let type_name = component_type.label();
+    let type_color = component_type.color();
 
     let border_color = if is_selected {
         "#f44336"
     } else if is_connect_target {
         "#FF5722"
-    } else if is_hovering && component_type == ComponentType::Container {
+    } else if is_hovering && component_type.is_container() {
         "#9C27B0"
     } else { 
         "#333" 
@@ -321,24 +1151,31 @@ fn ComponentBox(component_id: usize) -> Element {
     rsx! {
         div {
             class: "component-box",
+            id: "component-box-{component_id}",
             style: "
                 position: absolute;
                 left: {component_x}px;
                 top: {component_y}px;
-                width: 200px;
+                {box_width}
+                box-sizing: border-box;
                 background: {type_color};
                 border: {border_width} solid {border_color};
                 border-radius: 8px;
                 padding: 12px;
-                cursor: grab;
+                cursor: {cursor};
                 user-select: none;
                 box-shadow: {box_shadow};
+                transform: rotate({component_rotation}deg);
+                opacity: {box_opacity};
             ",
             // If connecting, clicking on a component finishes the connection, otherwise starts dragging
             onmousedown: move |e| {
                 e.stop_propagation();
-                if EDITOR_STATE.read().connecting_from.is_some() {
-                    // don't start dragging while connecting
+                if EDITOR_STATE.read().connecting_from.is_some() || EDITOR_STATE.read().eyedropper_active.is_some() {
+                    // don't start dragging while connecting or picking a color
+                } else if e.modifiers().shift() {
+                    // shift-click adds/removes from the multi-selection instead of dragging
+                    toggle_selection(component_id);
                 } else {
                     start_dragging(component_id, e.page_coordinates().x, e.page_coordinates().y);
                 }
@@ -347,25 +1184,27 @@ fn ComponentBox(component_id: usize) -> Element {
                 e.stop_propagation();
 
                 // Diagnostic log for clicks
-                #[cfg(target_arch = "wasm32")]
-                {
-                    let conn = { let s = EDITOR_STATE.read(); s.connecting_from };
-                    let jd = { let s = EDITOR_STATE.read(); s.just_dragged };
-                    web_sys::console::log_1(&format!("onclick: component {} clicked (connecting_from={:?}, just_dragged={})", component_id, conn, jd).into());
+                debug_log(|| {
+                    let s = EDITOR_STATE.read();
+                    format!("onclick: component {} clicked (connecting_from={:?}, just_dragged={})", component_id, s.connecting_from, s.just_dragged)
+                });
+
+                // the eyedropper takes the click instead of selecting this component
+                if pick_color(component_id) {
+                    return;
                 }
 
                 // If currently connecting, complete the connection even if just_dragged was recently set
-                if { let s = EDITOR_STATE.read(); s.connecting_from.is_some() } {
+                if EDITOR_STATE.read().connecting_from.is_some() {
                     // If there was a leftover just_dragged flag, clear it so the click isn't ignored
-                    if { let s = EDITOR_STATE.read(); s.just_dragged } {
+                    if EDITOR_STATE.read().just_dragged {
                         let mut s = EDITOR_STATE.write();
                         s.just_dragged = false;
                     }
 
                     if let Some(from_id) = { let s = EDITOR_STATE.read(); s.connecting_from } {
                         if from_id != component_id {
-                            #[cfg(target_arch = "wasm32")]
-                            { web_sys::console::log_1(&format!("onclick: completing connection {} -> {}", from_id, component_id).into()); }
+                            debug_log(|| format!("onclick: completing connection {} -> {}", from_id, component_id));
                             complete_connection(from_id, component_id);
                         }
                         stop_connecting();
@@ -375,7 +1214,7 @@ fn ComponentBox(component_id: usize) -> Element {
                 }
 
                 // Not connecting: handle standard click (ignore clicks immediately after dragging)
-                if { let s = EDITOR_STATE.read(); s.just_dragged } {
+                if EDITOR_STATE.read().just_dragged {
                     let mut s = EDITOR_STATE.write();
                     s.just_dragged = false;
                     return;
@@ -387,23 +1226,18 @@ fn ComponentBox(component_id: usize) -> Element {
             onmouseup: move |e| {
                 e.stop_propagation();
 
-                #[cfg(target_arch = "wasm32")]
-                {
-                    let conn = { let s = EDITOR_STATE.read(); s.connecting_from };
-                    web_sys::console::log_1(&format!("onmouseup: component {} (connecting_from={:?})", component_id, conn).into());
-                }
+                debug_log(|| format!("onmouseup: component {} (connecting_from={:?})", component_id, EDITOR_STATE.read().connecting_from));
 
-                if { let s = EDITOR_STATE.read(); s.connecting_from.is_some() } {
+                if EDITOR_STATE.read().connecting_from.is_some() {
                     // If there was a leftover just_dragged flag, clear it
-                    if { let s = EDITOR_STATE.read(); s.just_dragged } {
+                    if EDITOR_STATE.read().just_dragged {
                         let mut s = EDITOR_STATE.write();
                         s.just_dragged = false;
                     }
 
                     if let Some(from_id) = { let s = EDITOR_STATE.read(); s.connecting_from } {
                         if from_id != component_id {
-                            #[cfg(target_arch = "wasm32")]
-                            { web_sys::console::log_1(&format!("onmouseup: completing connection {} -> {}", from_id, component_id).into()); }
+                            debug_log(|| format!("onmouseup: completing connection {} -> {}", from_id, component_id));
                             complete_connection(from_id, component_id);
                         }
                         stop_connecting();
@@ -411,7 +1245,7 @@ fn ComponentBox(component_id: usize) -> Element {
                 }
             },
             onmouseenter: move |_| {
-                if component_type == ComponentType::Container {
+                if component_type.is_container() {
                     set_hovering_container(Some(component_id));
                 }
                 // if we're connecting, mark this as potential target
@@ -426,10 +1260,30 @@ fn ComponentBox(component_id: usize) -> Element {
 
             div {
                 style: "font-weight: bold; color: white; font-size: 14px; margin-bottom: 4px;",
-                "{type_name} #{component_id}"
+                TypeIcon { component_type: component_type.clone() }
+                " {type_name} #{component_id}"
+                if let Some(note) = component_note {
+                    span { title: "{note}", style: "float: right; cursor: help;", "📝" }
+                }
+                if is_locked {
+                    span { title: "Locked", style: "float: right;", "🔒" }
+                }
+                if let Some(master_id) = master_id {
+                    span { title: "Instance of #{master_id}", style: "float: right;", "◇" }
+                }
+                if component_type == ComponentType::RawHtml {
+                    span { title: "Unsanitized HTML: rendered and exported as-is", style: "float: right; cursor: help;", "⚠" }
+                }
+            }
+
+            if let Some(name) = component_name {
+                div {
+                    style: "color: white; font-size: 13px; font-style: italic; margin-bottom: 2px;",
+                    "{name}"
+                }
             }
 
-            if component_type == ComponentType::Container {
+            if component_type.is_container() {
                 div {
                     style: "color: rgba(255,255,255,0.8); font-size: 12px;",
                     "Children: {component_children_len}"
@@ -438,14 +1292,21 @@ fn ComponentBox(component_id: usize) -> Element {
                     div {
                         style: "margin-top: 8px; padding: 4px; background: rgba(255,255,255,0.2); 
                                 border-radius: 4px; text-align: center; font-size: 11px; color: white; cursor: pointer;",
+                        // pressing the button must not start dragging the box underneath
+                        onmousedown: move |e| e.stop_propagation(),
                         onclick: move |e| { e.stop_propagation(); start_connecting(component_id); },
                         if is_connecting_from_here { "🔗 Connecting..." } else { "🔗 Click to connect" }
                     }
                 }
+            } else if let Some(src) = image_src {
+                img {
+                    src: "{src}",
+                    draggable: "false",
+                    style: "display: block; max-width: 100%; max-height: 40px; pointer-events: none;",
+                }
             } else if !component_content.is_empty() {
                 div {
-                    style: "color: rgba(255,255,255,0.9); font-size: 12px; 
-                            overflow: hidden; text-overflow: ellipsis; white-space: nowrap;",
+                    style: "color: rgba(255,255,255,0.9); font-size: 12px; {content_wrap}",
                     "{component_content}"
                 }
             }
@@ -455,7 +1316,19 @@ fn ComponentBox(component_id: usize) -> Element {
 
 #[component]
 fn PropertiesPanel() -> Element {
+    // set while the opacity/rotation slider is being dragged, so the drag is one undo step
+    let mut opacity_edit = use_signal(|| false);
+    let mut rotation_edit = use_signal(|| false);
+    // likewise for typing a name, which is undone as a whole
+    let mut name_edit = use_signal(|| false);
     let state = EDITOR_STATE.read();
+
+    if state.selected_ids.len() > 1 {
+        return rsx! { BulkStylePanel {} };
+    }
+    if let Some((parent, child)) = state.selected_edge {
+        return rsx! { EdgePanel { parent, child } };
+    }
     
     let Some(selected_id) = state.selected_id else {
         return rsx! {
@@ -469,39 +1342,311 @@ fn PropertiesPanel() -> Element {
     let Some(component) = state.components.get(&selected_id) else {
         return rsx! { div { "Component not found" } };
     };
+    let children = component.children.iter()
+        .map(|&id| (id, state.components.get(&id).map(|c| c.component_type.label().to_string()).unwrap_or_default()))
+        .collect::<Vec<_>>();
+    let opacity_pct = component.styles.get("opacity")
+        .and_then(|v| v.trim().parse::<f64>().ok())
+        .map(|v| (v.clamp(0.0, 1.0) * 100.0).round())
+        .unwrap_or(100.0);
+    let rotation = rotation_deg(&component.styles);
+    // a gap in other units is shown as written
+    let gap_label = match stepper_gap(component) {
+        Some(gap) => format!("{}px", gap),
+        None => component.styles.get("gap").cloned().unwrap_or_default(),
+    };
+    let note = component.note.clone().unwrap_or_default();
+    let component_name = component.name.clone().unwrap_or_default();
+    let breadcrumb = state.document().ancestors(selected_id).into_iter()
+        .map(|id| (id, format!("{} #{}", state.components[&id].component_type.label(), id)))
+        .collect::<Vec<_>>();
+    let current_crumb = format!("{} #{}", component.component_type.label(), selected_id);
+    let is_root = breadcrumb.is_empty();
+    let has_children = !component.children.is_empty();
+    let created = format_timestamp(component.created_at);
+    let updated = format_timestamp(component.updated_at);
     
     rsx! {
         div { class: "properties-panel",
-            if component.component_type != ComponentType::Container {
+            // where the component lives: root container first, each crumb selects that ancestor
+            div { style: "padding: 12px 12px 0 12px; font-size: 12px; color: #666; display: flex; flex-wrap: wrap; gap: 4px;",
+                for (id, name) in breadcrumb {
+                    span {
+                        style: "cursor: pointer; text-decoration: underline;",
+                        onclick: move |_| select_component(id),
+                        "{name}"
+                    }
+                    span { "›" }
+                }
+                span { style: "font-weight: bold;", "{current_crumb}" }
+            }
+            div { style: "display: flex; gap: 8px; padding: 8px 12px 0 12px;",
+                button {
+                    disabled: is_root,
+                    title: "Esc",
+                    onclick: move |_| { select_parent(); },
+                    "Select parent"
+                }
+                button {
+                    disabled: !has_children,
+                    onclick: move |_| { select_first_child(); },
+                    "Select first child"
+                }
+            }
+
+            if component.component_type == ComponentType::Spacer {
+                div {
+                    style: "display:flex;flex-direction:column;padding-inline:12px;",
+                    h1 { style: "color:slate;text-align:center; margin: 24px 0 12px 0; font-size: 18px;", "Height" }
+
+                    label { style: "font-size: 12px; display: flex; align-items: center; gap: 6px;",
+                        input {
+                            r#type: "number",
+                            min: "0",
+                            style: "width: 80px;",
+                            value: "{spacer_height(&component)}",
+                            oninput: move |e| set_spacer_height(selected_id, &e.value()),
+                        }
+                        "px"
+                    }
+                }
+            } else if component.component_type == ComponentType::Divider {
+                // a rule has no content; its look comes from the styles below
+            } else if component.component_type == ComponentType::RawHtml {
+                div {
+                    style: "display:flex;flex-direction:column;padding-inline:12px;",
+                    h1 { style: "color:slate;text-align:center; margin: 24px 0 12px 0; font-size: 18px;", "HTML" }
+
+                    p { style: "margin: 0 0 8px 0; padding: 6px 8px; font-size: 12px; background: #fff3e0; border: 1px solid #ff9800; border-radius: 4px;",
+                        "⚠ Not sanitized: this HTML runs as-is in the preview and the exported page. Only paste code you trust."
+                    }
+                    textarea {
+                        rows: "8",
+                        style: "font-family: monospace; font-size: 12px;",
+                        placeholder: "<iframe src=\"…\"></iframe>",
+                        value: "{component.content}",
+                        oninput: move |e| update_content(selected_id, e.value()),
+                    }
+                }
+            } else if component.component_type == ComponentType::Image {
+                div {
+                    style: "display:flex;flex-direction:column;padding-inline:12px;",
+                    h1 { style: "color:slate;text-align:center; margin: 24px 0 12px 0; font-size: 18px;", "Image" }
+
+                    label { style: "font-size: 12px;", "Source URL" }
+                    input {
+                        r#type: "text",
+                        placeholder: "https://…",
+                        value: "{component.content}",
+                        oninput: move |e| update_content(selected_id, e.value()),
+                    }
+                    p { style: "margin: 6px 0 0 0; font-size: 12px; color: #666;", "The component's name is used as the alt text." }
+                }
+            } else if !component.component_type.is_container() {
                 div { 
                     style: "display:flex;flex-direction:column;padding-inline:12px;",
                     h1 { style: "color:slate;text-align:center; margin: 24px 0 12px 0; font-size: 18px;", "Content" }
 
+                    if supports_formatting(&component.component_type) {
+                        FormatToolbar { component_id: selected_id }
+                    }
                     input {
                         r#type: "text",
+                        id: "content-input-{selected_id}",
                         value: "{component.content}",
                         oninput: move |e| update_content(selected_id, e.value()),
                     }
+                    label { style: "font-size: 12px; display: flex; align-items: center; gap: 6px; margin-top: 8px;",
+                        input {
+                            r#type: "checkbox",
+                            checked: component.expand_content,
+                            onchange: move |e| set_expand_content(selected_id, e.checked()),
+                        }
+                        "Show full content on the canvas"
+                    }
+                    if component.component_type == ComponentType::Input {
+                        label { style: "font-size: 12px; display: flex; align-items: center; gap: 6px; margin-top: 8px;",
+                            "Input type"
+                            select {
+                                value: "{component.input_type.clone().unwrap_or_else(|| \"text\".to_string())}",
+                                onchange: move |e| set_input_type(selected_id, e.value()),
+                                for input_type in INPUT_TYPES.iter() {
+                                    option { value: "{input_type}", "{input_type}" }
+                                }
+                            }
+                        }
+                    }
+                    if component.component_type == ComponentType::Quote {
+                        label { style: "font-size: 12px; margin-top: 8px;", "Cite (source URL)" }
+                        input {
+                            r#type: "text",
+                            placeholder: "https://…",
+                            value: "{component.cite.clone().unwrap_or_default()}",
+                            oninput: move |e| update_cite(selected_id, e.value()),
+                        }
+                    }
                 }
-            }
-            
-            h1 { style: "color:slate;text-align:center; margin: 24px 0 12px 0; font-size: 18px;", "Styles" }
+            } else {
+                div { 
+                    style: "display:flex;flex-direction:column;padding-inline:12px;",
+                    h1 { style: "color:slate;text-align:center; margin: 24px 0 12px 0; font-size: 18px;", "Name" }
+
+                    input {
+                        r#type: "text",
+                        placeholder: "Editor-only, e.g. Hero section",
+                        value: "{component_name}",
+                        oninput: move |e| {
+                            push_history_once(name_edit);
+                            rename_component(selected_id, e.value());
+                        },
+                        onchange: move |_| name_edit.set(false),
+                    }
+                }
+            }
+            
+            h1 { style: "color:slate;text-align:center; margin: 24px 0 12px 0; font-size: 18px;", "Styles" }
+
+            label { style: "display: flex; align-items: center; gap: 8px; margin: 0 12px 12px 12px; font-size: 12px;",
+                "Opacity"
+                input {
+                    r#type: "range",
+                    min: "0",
+                    max: "100",
+                    style: "flex: 1;",
+                    value: "{opacity_pct}",
+                    oninput: move |e| {
+                        push_history_once(opacity_edit);
+                        set_opacity(selected_id, e.value().parse().unwrap_or(100.0));
+                    },
+                    onchange: move |_| opacity_edit.set(false),
+                }
+                "{opacity_pct}%"
+            }
+
+            label { style: "display: flex; align-items: center; gap: 8px; margin: 0 12px 12px 12px; font-size: 12px;",
+                "Rotation"
+                input {
+                    r#type: "range",
+                    min: "-180",
+                    max: "180",
+                    style: "flex: 1;",
+                    value: "{rotation}",
+                    oninput: move |e| {
+                        push_history_once(rotation_edit);
+                        set_rotation(selected_id, e.value().parse().unwrap_or(0.0));
+                    },
+                    onchange: move |_| rotation_edit.set(false),
+                }
+                "{rotation}°"
+            }
+            label { style: "display: flex; align-items: center; gap: 6px; margin: 0 12px 12px 12px; font-size: 12px;",
+                title: "Shift it from its place in the page by its offset from its parent on the canvas",
+                input {
+                    r#type: "checkbox",
+                    checked: component.relative_position,
+                    onchange: move |e| set_relative_position(selected_id, e.checked()),
+                }
+                "Relative position"
+            }
+
+            if component.component_type.is_container() {
+                div { style: "display: flex; align-items: center; gap: 8px; margin: 0 12px 12px 12px; font-size: 12px;",
+                    for (label, direction) in [("↓ Column", "column"), ("→ Row", "row")] {
+                        button {
+                            style: if flex_direction(component) == Some(direction) { "flex: 1; background: var(--color-primary);" } else { "flex: 1;" },
+                            aria_pressed: flex_direction(component) == Some(direction),
+                            onclick: move |_| set_flex_direction(selected_id, direction),
+                            "{label}"
+                        }
+                    }
+                    "Gap"
+                    button {
+                        aria_label: "Decrease gap",
+                        disabled: child_gap(component).is_none(),
+                        onclick: move |_| step_child_gap(selected_id, -GAP_STEP),
+                        "−"
+                    }
+                    span { style: "min-width: 36px; text-align: center;", "{gap_label}" }
+                    button {
+                        aria_label: "Increase gap",
+                        disabled: stepper_gap(component).is_none(),
+                        onclick: move |_| step_child_gap(selected_id, GAP_STEP),
+                        "+"
+                    }
+                }
+            }
+
+            div { style: "display: flex; gap: 8px; margin: 0 12px 12px 12px;",
+                for (label, property, value) in STYLE_PRESETS {
+                    button {
+                        style: if component.styles.contains_key(property) { "flex: 1; background: var(--color-primary);" } else { "flex: 1;" },
+                        aria_pressed: component.styles.contains_key(property),
+                        onclick: move |_| toggle_style_preset(selected_id, property, value),
+                        "{label}"
+                    }
+                }
+            }
             
+            ContrastBadge { component_id: selected_id }
+
             StyleInput { component_id: selected_id }
    
-            if component.component_type == ComponentType::Container {
+            if component.component_type.is_container() {
                 h4 { style: "margin: 24px 0 12px 12px; font-size: 14px;", "Children" }
-                div { style: "font-size: 12px; color: #666;margin: 12px 0 0 12px;",
+                div { style: "font-size: 12px; color: #666;margin: 12px 12px 0 12px;",
                     if component.children.is_empty() {
                         "No children yet"
                     } else {
-                        "Children: {component.children.len()}"
+                        for (child_id, child_label) in children {
+                            div { style: "display: flex; align-items: center; justify-content: space-between; margin-top: 4px;",
+                                "{child_label} #{child_id}"
+                                button {
+                                    style: "padding: 2px 8px; font-size: 12px;",
+                                    onclick: move |_| { let _ = disconnect_components(selected_id, child_id); },
+                                    "Disconnect"
+                                }
+                            }
+                        }
                     }
                 }
             }
             
+            h4 { style: "margin: 24px 0 8px 12px; font-size: 14px;", if component.component_type == ComponentType::Link { "Anchor & link" } else { "Anchor" } }
+            AnchorPanel { component_id: selected_id }
+
+            h4 { style: "margin: 24px 0 8px 12px; font-size: 14px;", "Symbol" }
+            SymbolPanel { component_id: selected_id }
+
+            h4 { style: "margin: 24px 0 8px 12px; font-size: 14px;", "Note" }
+            div { style: "display: flex; flex-direction: column; padding-inline: 12px;",
+                textarea {
+                    rows: "3",
+                    placeholder: "Visible in the editor only, never exported",
+                    value: "{note}",
+                    oninput: move |e| update_note(selected_id, e.value()),
+                }
+            }
+
+            div { style: "margin: 24px 12px 0 12px; font-size: 12px; color: #666;",
+                "Created: {created}"
+                br {}
+                "Updated: {updated}"
+            }
+
             div { style: "margin-top: 24px; padding-inline: 12px",
                 button {
+                    onclick: move |_| center_on(selected_id),
+                    style: "width: 100%; padding: 8px; margin-bottom: 8px; cursor: pointer;",
+                    "Center in view"
+                }
+                button {
+                    title: "Copy this component and everything inside it (Ctrl+D)",
+                    onclick: move |_| { duplicate_component(selected_id); },
+                    style: "width: 100%; padding: 8px; margin-bottom: 8px; cursor: pointer;",
+                    "Duplicate"
+                }
+                button {
+                    disabled: component.locked,
                     onclick: move |_| delete_component(selected_id),
                     style: "width: 100%; padding: 8px; cursor: pointer; 
                             background: #f44336; color: white; border: none; border-radius: 4px;",
@@ -512,77 +1657,474 @@ fn PropertiesPanel() -> Element {
     }
 }
 
+const OUTLINE_STYLE: &str = "border: 1px dashed #888; padding: 8px; margin: 4px; color: #333;";
+const OUTLINE_LABEL_STYLE: &str = "display: block; font-size: 10px; font-weight: normal; color: #888; margin-bottom: 4px;";
+
+// Shown instead of the single-component properties when several components are selected
 #[component]
-fn PreviewCanvas() -> Element {
-    let state = EDITOR_STATE.read();
+fn BulkStylePanel() -> Element {
+    let mut property = use_signal(String::new);
+    let mut value = use_signal(String::new);
+    let count = EDITOR_STATE.read().selected_ids.len();
+
+    rsx! {
+        div { class: "properties-panel", style: "display:flex;flex-direction:column;gap:8px;padding-inline:12px;",
+            h1 { style: "color:slate;text-align:center; margin: 24px 0 12px 0; font-size: 18px;", "{count} components selected" }
+            input {
+                placeholder: "property (e.g. color)",
+                value: "{property}",
+                oninput: move |e| property.set(e.value()),
+            }
+            input {
+                placeholder: "value (empty removes it)",
+                value: "{value}",
+                oninput: move |e| value.set(e.value()),
+            }
+            button {
+                disabled: property().trim().is_empty(),
+                onclick: move |_| apply_style_to_selection(property().trim(), value()),
+                "Apply to {count} selected"
+            }
+        }
+    }
+}
+
+// Properties of the connection arrow selected on the canvas
+#[component]
+fn EdgePanel(parent: usize, child: usize) -> Element {
+    let style = edge_style(&EDITOR_STATE.read(), parent, child);
+
+    rsx! {
+        div { class: "properties-panel", style: "display:flex;flex-direction:column;gap:8px;padding-inline:12px;",
+            h1 { style: "color:slate;text-align:center; margin: 24px 0 12px 0; font-size: 18px;", "Connection #{parent} → #{child}" }
+            label { style: "display: flex; align-items: center; gap: 8px; font-size: 12px;",
+                "Color"
+                input {
+                    r#type: "color",
+                    value: "{style.color}",
+                    // onchange fires once the picker closes, so one pick is one undo step
+                    onchange: {
+                        let style = style.clone();
+                        move |e: Event<FormData>| set_edge_style(parent, child, EdgeStyle { color: e.value(), ..style.clone() })
+                    },
+                }
+            }
+            label { style: "display: flex; align-items: center; gap: 8px; font-size: 12px;",
+                "Line"
+                select {
+                    value: if style.dashed { "dashed" } else { "solid" },
+                    onchange: {
+                        let style = style.clone();
+                        move |e: Event<FormData>| set_edge_style(parent, child, EdgeStyle { dashed: e.value() == "dashed", ..style.clone() })
+                    },
+                    option { value: "solid", "Solid" }
+                    option { value: "dashed", "Dashed" }
+                }
+            }
+            button {
+                onclick: move |_| set_edge_style(parent, child, EdgeStyle::default()),
+                "Reset style"
+            }
+            button {
+                onclick: move |_| { let _ = disconnect_components(parent, child); },
+                style: "padding: 8px; cursor: pointer; background: #f44336; color: white; border: none; border-radius: 4px;",
+                "Remove connection"
+            }
+        }
+    }
+}
+
+// The preview wrapper stands in for the exported <body>
+const PREVIEW_ROOT_SELECTOR: &str = ".preview-root";
+
+#[component]
+fn PreviewCanvas(outline: bool) -> Element {
+    // memos only re-render the page when its own inputs change, not on every edit
+    let roots = use_memo(|| EDITOR_STATE.read().document().roots());
+    let page = use_memo(|| {
+        let state = EDITOR_STATE.read();
+        let reset = if state.css_reset.enabled { scope_css(&state.css_reset.css, PREVIEW_ROOT_SELECTOR) } else { String::new() };
+        (style_string(&state.page_styles), state.preview_background.clone(), reset)
+    });
+    let (page_style, background, reset) = page();
+    let (page_style, reset) = if outline { (String::new(), String::new()) } else { (page_style, reset) };
     
     rsx! {
         div {
-            style: "width: 100%; height: 100%; background: white; overflow-y: auto;",
+            class: "preview-root",
+            style: "width: 100%; height: 100%; background: {background}; overflow-y: auto; {page_style}",
+            // scoped to the preview so it leaves the editor's own UI alone
+            if !reset.is_empty() {
+                style { "{reset}" }
+            }
             
-            for (id, component) in state.components.iter().filter(|(_, c)| {
-                !state.components.values().any(|comp| comp.children.contains(&c.id))
-            }) {
-                PreviewComponent { component_id: *id }
+            for id in roots() {
+                PreviewComponent { key: "{id}", component_id: id, outline }
             }
         }
     }
 }
 
-#[component]
-fn PreviewComponent(component_id: usize) -> Element {
-    let state = EDITOR_STATE.read();
-    let component = state.components.get(&component_id).unwrap();
-    
-    let style_str = component.styles.iter()
+// Inline `style` attribute value for a styles map
+pub fn style_string(styles: &HashMap<String, String>) -> String {
+    styles.iter()
         .map(|(k, v)| format!("{}: {};", k, v))
         .collect::<Vec<_>>()
-        .join(" ");
+        .join(" ")
+}
+
+// Renders a component and its children; in outline mode styles are swapped for a wireframe box labeled with type/id
+#[component]
+fn PreviewComponent(component_id: usize, outline: bool) -> Element {
+    // subscribe to this component's data only: editing another component doesn't re-render this one
+    let component = use_memo(use_reactive!(|component_id| EDITOR_STATE.read().components.get(&component_id).cloned()));
+    // offsets of a relatively positioned component, which also depend on its parent's position
+    let offset = use_memo(use_reactive!(|component_id| position_offset(&EDITOR_STATE.read(), component_id)));
+    // the style string is rebuilt only when the styles themselves change, not on content edits
+    let style_map = use_memo(move || {
+        let mut styles = component.read().as_ref().map(|c| c.styles.clone()).unwrap_or_default();
+        if let Some(offset) = offset() {
+            styles.extend(position_styles(offset).map(|(k, v)| (k.to_string(), v)));
+        }
+        styles
+    });
+    let styles = use_memo(move || style_string(&style_map.read()));
+    // a Link's href depends on its target's anchor; memoized so other edits don't re-render the link
+    let href = use_memo(use_reactive!(|component_id| link_href(&EDITOR_STATE.read(), component_id)));
+    let component = component.read();
+    let Some(component) = component.as_ref().filter(|c| c.visible) else {
+        return rsx! {};
+    };
+    
+    let style_str = if outline { OUTLINE_STYLE.to_string() } else { styles() };
+    let outline_label = format!("{} #{}", component.component_type.label(), component_id);
+    // in-page link target; duplicates are only dropped on export
+    let anchor = component.anchor_id.clone();
     
-    match component.component_type {
+    match &component.component_type {
         ComponentType::Container => rsx! {
-            div { style: "{style_str}",
+            div { style: "{style_str}", id: anchor.clone(),
+                if outline {
+                    span { style: OUTLINE_LABEL_STYLE, "{outline_label}" }
+                }
                 for child_id in component.children.iter() {
-                    PreviewComponent { component_id: *child_id }
+                    PreviewComponent { key: "{child_id}", component_id: *child_id, outline }
                 }
             }
         },
         ComponentType::Heading => rsx! {
-            h1 { style: "{style_str}", "{component.content}" }
+            h1 { style: "{style_str}", id: anchor.clone(),
+                if outline {
+                    span { style: OUTLINE_LABEL_STYLE, "{outline_label}" }
+                }
+                span { dangerous_inner_html: "{inline_html(&component.content)}" }
+            }
         },
         ComponentType::Paragraph => rsx! {
-            p { style: "{style_str}", "{component.content}" }
+            p { style: "{style_str}", id: anchor.clone(),
+                if outline {
+                    span { style: OUTLINE_LABEL_STYLE, "{outline_label}" }
+                }
+                span { dangerous_inner_html: "{inline_html(&component.content)}" }
+            }
         },
+        ComponentType::Quote => rsx! {
+            blockquote { style: "{style_str}", id: anchor.clone(), cite: component.cite.clone(),
+                if outline {
+                    span { style: OUTLINE_LABEL_STYLE, "{outline_label}" }
+                }
+                "{component.content}"
+            }
+        },
+        // preview forms never submit (that would navigate away from the editor)
+        ComponentType::Form => rsx! {
+            form { style: "{style_str}", id: anchor.clone(), onsubmit: move |e| e.prevent_default(),
+                if outline {
+                    span { style: OUTLINE_LABEL_STYLE, "{outline_label}" }
+                }
+                for child_id in component.children.iter() {
+                    PreviewComponent { key: "{child_id}", component_id: *child_id, outline }
+                }
+            }
+        },
+        ComponentType::Input => {
+            let input_type = component.input_type.clone().unwrap_or_else(|| "text".to_string());
+            if outline {
+                // an <input> can't hold the label, so the wireframe box wraps it
+                rsx! {
+                    div { style: "{style_str}", id: anchor.clone(),
+                        span { style: OUTLINE_LABEL_STYLE, "{outline_label}" }
+                        input { r#type: "{input_type}", placeholder: "{component.content}" }
+                    }
+                }
+            } else {
+                rsx! {
+                    input { style: "{style_str}", id: anchor.clone(), r#type: "{input_type}", placeholder: "{component.content}" }
+                }
+            }
+        }
+        ComponentType::Divider => {
+            if outline {
+                // like <input>, an <hr> can't hold the label
+                rsx! {
+                    div { style: "{style_str}", id: anchor.clone(),
+                        span { style: OUTLINE_LABEL_STYLE, "{outline_label}" }
+                        hr {}
+                    }
+                }
+            } else {
+                rsx! { hr { style: "{style_str}", id: anchor.clone() } }
+            }
+        }
+        ComponentType::Spacer => rsx! {
+            div { style: "{style_str}", id: anchor.clone(),
+                if outline {
+                    span { style: OUTLINE_LABEL_STYLE, "{outline_label}" }
+                }
+            }
+        },
+        // unsanitized on purpose: it's the escape hatch for embeds
+        ComponentType::RawHtml => rsx! {
+            div { style: "{style_str}", id: anchor.clone(),
+                if outline {
+                    span { style: OUTLINE_LABEL_STYLE, "{outline_label}" }
+                }
+                div { dangerous_inner_html: "{component.content}" }
+            }
+        },
+        ComponentType::Image => {
+            let alt = component.name.clone().unwrap_or_default();
+            if outline {
+                // like <input>, an <img> can't hold the label
+                rsx! {
+                    div { style: "{style_str}", id: anchor.clone(),
+                        span { style: OUTLINE_LABEL_STYLE, "{outline_label}" }
+                        img { src: "{component.content}", alt: "{alt}", style: "max-width: 100%;" }
+                    }
+                }
+            } else {
+                rsx! { img { style: "{style_str}", id: anchor.clone(), src: "{component.content}", alt: "{alt}" } }
+            }
+        }
+        ComponentType::Link => rsx! {
+            a { style: "{style_str}", id: anchor.clone(), href: "{href}",
+                if outline {
+                    span { style: OUTLINE_LABEL_STYLE, "{outline_label}" }
+                }
+                "{component.content}"
+            }
+        },
+        // custom types render through their registered renderer inside a wrapper carrying the styles
+        ComponentType::Custom(name) => {
+            let rendered = renderer_for(name).preview(component);
+            rsx! {
+                div { style: "{style_str}", id: anchor.clone(),
+                    if outline {
+                        span { style: OUTLINE_LABEL_STYLE, "{outline_label}" }
+                    }
+                    {rendered}
+                }
+            }
+        }
     }
 }
 
-fn add_component(component_type: ComponentType) {
-    let mut state = EDITOR_STATE.write();
+fn add_component(component_type: ComponentType) -> usize {
+    push_history();
+    insert_component(&mut EDITOR_STATE.write(), component_type)
+}
+
+// add_component without the undo step, for callers that record their own
+fn insert_component(state: &mut EditorState, component_type: ComponentType) -> usize {
+    // the id document_mut().add hands out below
     let id = state.next_id;
-    state.next_id += 1;
     
+    // number fresh text components per type ("Heading 1", "Heading 2", ...) so they're distinguishable
+    let same_type = state.components.values().filter(|c| c.component_type == component_type).count();
     let default_content = match component_type {
-        ComponentType::Heading => "Heading Text".to_string(),
-        ComponentType::Paragraph => "Paragraph text".to_string(),
-        ComponentType::Container => String::new(),
+        ComponentType::Heading => format!("Heading {}", same_type + 1),
+        ComponentType::Paragraph => format!("Paragraph {}", same_type + 1),
+        ComponentType::Quote => format!("Quote {}", same_type + 1),
+        ComponentType::Input => format!("Input {}", same_type + 1),
+        ComponentType::Link => format!("Link {}", same_type + 1),
+        ComponentType::Container | ComponentType::Form | ComponentType::Divider | ComponentType::Spacer
+            | ComponentType::RawHtml | ComponentType::Image | ComponentType::Custom(_) => String::new(),
     };
+    // the type's configured defaults, then (when reusing) the last styles used for the type on top
+    let mut styles = default_styles(&component_type);
+    if state.sticky_styles {
+        styles.extend(LAST_STYLES.read().get(component_type.label()).cloned().unwrap_or_default());
+    }
+    // a spacer without a height would collapse to nothing
+    if component_type == ComponentType::Spacer {
+        styles.entry("height".to_string()).or_insert_with(|| format!("{}px", DEFAULT_SPACER_HEIGHT));
+    }
     
-    let component = Component {
-        id,
-        component_type,
-        children: Vec::new(),
-        styles: HashMap::new(),
-        content: default_content,
-        x: 50.0 + (id as f64 * 20.0),
-        y: 50.0 + (id as f64 * 20.0),
-    };
+    let mut component = Component::new(id, component_type, 50.0 + (id as f64 * 20.0), 50.0 + (id as f64 * 20.0));
+    component.styles = styles;
+    component.content = default_content;
     
-    state.components.insert(id, component);
-    state.selected_id = Some(id);
+    let id = state.document_mut().add(component);
+    state.select(Some(id));
+    id
+}
+
+// Put the selected components into a new container as one undoable step and select the container.
+// Only the top of the selection moves (members whose parent is also selected keep it). If they all shared
+// one parent, the container takes their place under it. The canvas draws children as separate boxes joined
+// to their parent by arrows, not nested inside it, so the container goes just above the selection like any
+// other parent rather than around it.
+pub fn wrap_selection_in_container() -> Option<usize> {
+    let (members, common_parent, min_x, min_y) = {
+        let state = EDITOR_STATE.read();
+        let mut members = state.selected_ids.iter()
+            .copied()
+            .filter(|id| state.components.contains_key(id))
+            .filter(|&id| state.document().parent_of(id).is_none_or(|p| !state.selected_ids.contains(&p)))
+            .collect::<Vec<_>>();
+        if members.is_empty() {
+            return None;
+        }
+        // children keep reading order: top to bottom, then left to right
+        members.sort_by(|a, b| {
+            let (a, b) = (&state.components[a], &state.components[b]);
+            a.y.total_cmp(&b.y).then(a.x.total_cmp(&b.x))
+        });
+        let parents = members.iter().map(|&id| state.document().parent_of(id)).collect::<Vec<_>>();
+        let common_parent = parents[0].filter(|_| parents.iter().all(|p| *p == parents[0]));
+        let min_x = members.iter().map(|id| state.components[id].x).fold(f64::INFINITY, f64::min);
+        let min_y = members.iter().map(|id| state.components[id].y).fold(f64::INFINITY, f64::min);
+        (members, common_parent, min_x, min_y)
+    };
+
+    // add_component records the undo step, so everything below is undone together with it
+    let container = add_component(ComponentType::Container);
+    let mut state = EDITOR_STATE.write();
+    // the container takes the slot of the first member under their shared parent
+    let slot = common_parent.and_then(|p| state.document().children_of(p).iter().position(|c| members.contains(c)));
+    for &member in members.iter() {
+        // a child policy may keep some members out; they stay where they are
+        let _ = state.document_mut().move_to(member, Some(container), usize::MAX);
+    }
+    if let (Some(parent), Some(slot)) = (common_parent, slot) {
+        let _ = state.document_mut().move_to(container, Some(parent), slot);
+    }
+    if let Some(comp) = state.components.get_mut(&container) {
+        comp.x = min_x.round();
+        comp.y = (min_y - DEFAULT_BOX_HEIGHT - 40.0).round();
+    }
+    state.select(Some(container));
+    Some(container)
+}
+
+fn end_palette_drag() {
+    let mut state = EDITOR_STATE.write();
+    state.palette_drag = None;
+    state.palette_ghost = None;
+}
+
+// Add the dragged toolbox type centered on the drop point; dropping onto a container makes it a child
+fn drop_palette_component(page_x: f64, page_y: f64) {
+    let Some(component_type) = EDITOR_STATE.read().palette_drag.clone() else { return };
+    end_palette_drag();
+
+    let (x, y) = page_to_local(page_x, page_y);
+    let parent = {
+        let state = EDITOR_STATE.read();
+        state.component_at(x, y, |c| c.component_type.is_container())
+    };
+
+    // one undo step for the new component and its connection
+    push_history();
+    let mut state = EDITOR_STATE.write();
+    let id = insert_component(&mut state, component_type);
+    let precision = state.coord_precision;
+    if let Some(component) = state.components.get_mut(&id) {
+        component.x = round_coord(x - BOX_WIDTH / 2.0, precision);
+        component.y = round_coord(y - DEFAULT_BOX_HEIGHT / 2.0, precision);
+    }
+    if let Some(parent) = parent {
+        // a child policy may refuse it; it's left as a root then
+        let _ = state.document_mut().connect(parent, id);
+    }
+}
+
+pub fn select_component(id: usize) {
+    EDITOR_STATE.write().select(Some(id));
+}
+
+// Move the selection to the selected component's parent container; returns false at a root
+pub fn select_parent() -> bool {
+    let parent = {
+        let state = EDITOR_STATE.read();
+        state.selected_id.and_then(|id| state.document().parent_of(id))
+    };
+    match parent {
+        Some(parent) => { select_component(parent); true }
+        None => false,
+    }
+}
+
+pub fn select_first_child() -> bool {
+    let child = {
+        let state = EDITOR_STATE.read();
+        state.selected_id
+            .and_then(|id| state.components.get(&id))
+            .and_then(|c| c.children.first().copied())
+    };
+    match child {
+        Some(child) => { select_component(child); true }
+        None => false,
+    }
+}
+
+// Move the selection to the next (or previous) component in document order, wrapping around, and bring it
+// into view. With nothing selected it starts at the first (or last) component.
+pub fn select_next(backwards: bool) -> bool {
+    let next = {
+        let state = EDITOR_STATE.read();
+        let order = state.document().tree_order();
+        if order.is_empty() {
+            return false;
+        }
+        let len = order.len();
+        match state.selected_id.and_then(|id| order.iter().position(|&o| o == id)) {
+            Some(i) if backwards => order[(i + len - 1) % len],
+            Some(i) => order[(i + 1) % len],
+            None if backwards => order[len - 1],
+            None => order[0],
+        }
+    };
+    select_component(next);
+    scroll_into_view(next);
+    true
+}
+
+fn select_edge(edge: (usize, usize)) {
+    let mut state = EDITOR_STATE.write();
+    state.select(None);
+    state.selected_edge = Some(edge);
 }
 
-fn select_component(id: usize) {
-    EDITOR_STATE.write().selected_id = Some(id);
+// SVG marker id for an arrowhead of the given color ("arrowhead" for the default color)
+fn arrowhead_id(color: &str) -> String {
+    if color == EdgeStyle::default().color {
+        return "arrowhead".to_string();
+    }
+    let suffix = color.chars().filter(|c| c.is_ascii_alphanumeric()).collect::<String>();
+    format!("arrowhead-{}", suffix)
+}
+
+pub fn toggle_selection(id: usize) {
+    let mut state = EDITOR_STATE.write();
+    state.selected_edge = None;
+    if let Some(pos) = state.selected_ids.iter().position(|&s| s == id) {
+        state.selected_ids.remove(pos);
+        state.selected_id = state.selected_ids.last().copied();
+    } else {
+        state.selected_ids.push(id);
+        state.selected_id = Some(id);
+    }
 }
 
 fn start_dragging(id: usize, mouse_x: f64, mouse_y: f64) {
@@ -590,43 +2132,117 @@ fn start_dragging(id: usize, mouse_x: f64, mouse_y: f64) {
     let (local_x, local_y) = page_to_local(mouse_x, mouse_y);
 
     // compute offsets without holding a write lock
-    let (offset_x, offset_y) = if let Some(component) = EDITOR_STATE.read().components.get(&id) {
-        (local_x - component.x, local_y - component.y)
+    let (offset_x, offset_y, start_x, start_y, locked) = if let Some(component) = EDITOR_STATE.read().components.get(&id) {
+        (local_x - component.x, local_y - component.y, component.x, component.y, component.locked)
     } else {
         return;
     };
 
     let mut state = EDITOR_STATE.write();
+    // keep an existing multi-selection when dragging one of its members
+    if !state.selected_ids.contains(&id) {
+        state.select(Some(id));
+    }
+    // locked components can still be selected, just not moved
+    if locked {
+        return;
+    }
+
     state.dragging_id = Some(id);
     state.drag_offset_x = offset_x;
     state.drag_offset_y = offset_y;
-    state.selected_id = Some(id);
+    state.drag_start_x = start_x;
+    state.drag_start_y = start_y;
+}
+
+// Window-level event listener that lasts as long as the guard: VisualEditor holds one per listener while
+// it's mounted, and dropping them on unmount removes the listeners, so remounting doesn't stack handlers
+// or leave them acting on a stale editor. Outside the browser there is no window and nothing is attached.
+#[derive(Default)]
+pub struct WindowListener {
+    #[cfg(target_arch = "wasm32")]
+    listener: Option<(&'static str, EventClosure)>,
+}
+
+#[cfg(target_arch = "wasm32")]
+type EventClosure = wasm_bindgen::prelude::Closure<dyn FnMut(web_sys::Event)>;
+
+impl WindowListener {
+    pub fn new(event: &'static str, handler: impl FnMut(web_sys::Event) + 'static) -> Self {
+        #[cfg(target_arch = "wasm32")]
+        {
+            use wasm_bindgen::JsCast;
+            let listener = web_sys::window().map(|window| {
+                let closure = wasm_bindgen::prelude::Closure::wrap(Box::new(handler) as Box<dyn FnMut(web_sys::Event)>);
+                let _ = window.add_event_listener_with_callback(event, closure.as_ref().unchecked_ref());
+                (event, closure)
+            });
+            WindowListener { listener }
+        }
 
-    // Attach a global window-level mouseup listener once so releasing outside the canvas also stops dragging
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            let _ = (event, handler);
+            WindowListener {}
+        }
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+impl Drop for WindowListener {
+    fn drop(&mut self) {
+        use wasm_bindgen::JsCast;
+        if let (Some(window), Some((event, closure))) = (web_sys::window(), self.listener.take()) {
+            let _ = window.remove_event_listener_with_callback(event, closure.as_ref().unchecked_ref());
+        }
+    }
+}
+
+// Measure every rendered editor box in one pass and store the sizes that changed with a single write.
+// offsetWidth/offsetHeight ignore transforms, so rotated or zoomed boxes report their layout size.
+fn measure_component_sizes() {
     #[cfg(target_arch = "wasm32")]
     {
         use wasm_bindgen::JsCast;
-        if !WINDOW_MOUSEUP_INSTALLED.load(Ordering::SeqCst) {
-            if let Some(window) = web_sys::window() {
-                let closure = wasm_bindgen::prelude::Closure::wrap(Box::new(move |_: web_sys::Event| {
-                    stop_dragging();
-                }) as Box<dyn FnMut(web_sys::Event)>);
-                let _ = window.add_event_listener_with_callback("mouseup", closure.as_ref().unchecked_ref());
-                // keep it alive permanently (single global handler)
-                closure.forget();
-                WINDOW_MOUSEUP_INSTALLED.store(true, Ordering::SeqCst);
+        let Some(document) = web_sys::window().and_then(|w| w.document()) else { return };
+        let changed = {
+            let state = EDITOR_STATE.read();
+            state.components.values().filter_map(|c| {
+                let elem = document.get_element_by_id(&format!("component-box-{}", c.id))?
+                    .dyn_into::<web_sys::HtmlElement>().ok()?;
+                let (width, height) = (elem.offset_width() as f64, elem.offset_height() as f64);
+                ((c.width - width).abs() > 0.5 || (c.height - height).abs() > 0.5).then_some((c.id, width, height))
+            }).collect::<Vec<_>>()
+        };
+        if changed.is_empty() {
+            return;
+        }
+
+        let mut state = EDITOR_STATE.write();
+        for (id, width, height) in changed {
+            if let Some(component) = state.components.get_mut(&id) {
+                component.width = width;
+                component.height = height;
             }
         }
     }
+
+    // Non-web targets: no DOM to measure, keep the default size
 }
 
-// Convert page coordinates to coordinates local to the canvas element (id="canvas").
+// Convert page coordinates to canvas coordinates: relative to the canvas element (id="canvas"), with the viewport zoom/pan undone.
 fn page_to_local(page_x: f64, page_y: f64) -> (f64, f64) {
+    let (x, y) = page_to_screen(page_x, page_y);
+    screen_to_canvas(x, y)
+}
+
+// Page coordinates relative to the canvas element, before zoom/pan. Only touches the DOM, never EDITOR_STATE.
+fn page_to_screen(page_x: f64, page_y: f64) -> (f64, f64) {
     #[cfg(target_arch = "wasm32")]
     {
         if let Some(window) = web_sys::window() {
             if let Some(document) = window.document() {
-                if let Some(elem) = document.get_element_by_id("canvas") {
+                if let Some(elem) = document.get_element_by_id(CANVAS_ID) {
                     let rect = elem.get_bounding_client_rect();
                     // rect.left/top are relative to the viewport; page coordinates include scroll offset
                     let scroll_x = window.page_x_offset().unwrap_or(0.0);
@@ -642,54 +2258,63 @@ fn page_to_local(page_x: f64, page_y: f64) -> (f64, f64) {
 
     #[cfg(not(target_arch = "wasm32"))]
     {
-        // Non-web targets: assume coordinates are already local
+        // Non-web targets: assume coordinates are already relative to the canvas
         (page_x, page_y)
     }
 }
 
-// Updated to also handle connecting mouse movement & hover detection, using local coordinates and separating reads/writes
-fn handle_mouse_move(page_mouse_x: f64, page_mouse_y: f64) {
-    let (mouse_x, mouse_y) = page_to_local(page_mouse_x, page_mouse_y);
-
-    // Handle dragging by reading minimal state first, then performing a focused write
-    if let Some(id) = { let s = EDITOR_STATE.read(); s.dragging_id } {
-        let (drag_x, drag_y) = { let s = EDITOR_STATE.read(); (s.drag_offset_x, s.drag_offset_y) };
-        let new_x = mouse_x - drag_x;
-        let new_y = mouse_y - drag_y;
-        #[cfg(target_arch = "wasm32")]
-        {
-            web_sys::console::log_1(&format!("handle_mouse_move: attempting write to move id={} to {} {}", id, new_x, new_y).into());
-        }
-        let mut s = EDITOR_STATE.write();
-        if let Some(component) = s.components.get_mut(&id) {
-            component.x = new_x;
-            component.y = new_y;
-        }
-    }
-
-    // Update connecting preview position and hovered target
-    if { let s = EDITOR_STATE.read(); s.connecting_from.is_some() } {
-        // compute hovered target under mouse using a read lock
-        let hovered = { 
-            let s = EDITOR_STATE.read();
-            s.components.iter().find_map(|(&id, comp)| {
-                if s.connecting_from == Some(id) { return None; }
-                let left = comp.x;
-                let right = comp.x + 200.0;
-                let top = comp.y;
-                let bottom = comp.y + 80.0;
-                if mouse_x >= left && mouse_x <= right && mouse_y >= top && mouse_y <= bottom {
-                    Some(id)
-                } else { None }
+// Handles dragging and the connecting preview (position + hovered target) in canvas coordinates.
+// Everything is computed under one read lock and applied with a single write, and plain hovering
+// (no drag, no connection in progress) doesn't write at all. Holding Shift while dragging locks movement
+// to the dominant axis since the drag started.
+// Locking discipline (mousemove fires constantly, so keep it to two short borrows per event):
+// 1. one read guard, dropped at the end of its block, collects everything the update needs;
+// 2. nothing is written when neither a drag nor a connection is in progress;
+// 3. one write guard applies the whole update, with no other EDITOR_STATE access while it's held.
+// Helpers that lock on their own (screen_to_canvas, component setters) must not be called inside either block.
+fn handle_mouse_move(page_mouse_x: f64, page_mouse_y: f64, shift: bool) {
+    let (screen_x, screen_y) = page_to_screen(page_mouse_x, page_mouse_y);
+
+    let (mouse_x, mouse_y, drag_to, connect_hover) = {
+        let s = EDITOR_STATE.read();
+        let (mouse_x, mouse_y) = ((screen_x - s.pan_x) / s.zoom, (screen_y - s.pan_y) / s.zoom);
+        let drag_to = s.dragging_id.map(|id| {
+            let mut new_x = mouse_x - s.drag_offset_x;
+            let mut new_y = mouse_y - s.drag_offset_y;
+            if shift {
+                if (new_x - s.drag_start_x).abs() >= (new_y - s.drag_start_y).abs() {
+                    new_y = s.drag_start_y;
+                } else {
+                    new_x = s.drag_start_x;
+                }
+            }
+            (id, new_x, new_y)
+        });
+        // the box under the pointer wins; otherwise a near miss snaps to the closest box the connection could go to
+        let connect_hover = s.connecting_from.map(|from| {
+            s.component_at(mouse_x, mouse_y, |comp| comp.id != from).or_else(|| {
+                s.nearest_component(mouse_x, mouse_y, CONNECT_SNAP_DISTANCE / s.zoom, |comp| can_connect(&s, from, comp.id).is_ok())
             })
-        };
+        });
+        (mouse_x, mouse_y, drag_to, connect_hover)
+    };
+    if drag_to.is_none() && connect_hover.is_none() {
+        return;
+    }
 
-        #[cfg(target_arch = "wasm32")]
-        {
-            web_sys::console::log_1(&format!("handle_mouse_move: updating connecting mouse to {} {}, hovered={:?}", mouse_x, mouse_y, hovered).into());
-        }
+    debug_log(|| format!("handle_mouse_move: drag={:?} connecting_hover={:?} at {} {}", drag_to, connect_hover, mouse_x, mouse_y));
 
-        let mut s = EDITOR_STATE.write();
+    let mut s = EDITOR_STATE.write();
+    if let Some((id, new_x, new_y)) = drag_to {
+        s.mark_changed();
+        let precision = s.coord_precision;
+        if let Some(component) = s.components.get_mut(&id) {
+            component.x = round_coord(new_x, precision);
+            component.y = round_coord(new_y, precision);
+            component.touch();
+        }
+    }
+    if let Some(hovered) = connect_hover {
         s.connecting_mouse_x = mouse_x;
         s.connecting_mouse_y = mouse_y;
         s.connecting_hover_target_id = hovered;
@@ -699,9 +2324,7 @@ fn handle_mouse_move(page_mouse_x: f64, page_mouse_y: f64) {
 fn stop_dragging() {
     // Try to clear immediately; if there's a borrow conflict, fall back to scheduling on next tick
     let immediate_ok = std::panic::catch_unwind(|| {
-        let mut s = EDITOR_STATE.write();
-        s.dragging_id = None;
-        s.just_dragged = true;
+        EDITOR_STATE.write().end_drag();
     }).is_ok();
 
     if immediate_ok {
@@ -716,16 +2339,11 @@ fn stop_dragging() {
             // clone window for use inside closures so we don't move `window`
             let window_clone = window.clone();
             let attempt = wasm_bindgen::prelude::Closure::wrap(Box::new(move || {
-                #[cfg(target_arch = "wasm32")]
-                {
-                    web_sys::console::log_1(&"stop_dragging: attempt write".into());
-                }
+                debug_log(|| "stop_dragging: attempt write".to_string());
 
                 // Try to write; if it panics because the signal is borrowed, reschedule another attempt
                 let ok = std::panic::catch_unwind(|| {
-                    let mut s = EDITOR_STATE.write();
-                    s.dragging_id = None;
-                    s.just_dragged = true;
+                    EDITOR_STATE.write().end_drag();
                 });
 
                 if ok.is_err() {
@@ -733,9 +2351,7 @@ fn stop_dragging() {
                     let window_retry = window_clone.clone();
                     let retry = wasm_bindgen::prelude::Closure::wrap(Box::new(move || {
                         let _ = std::panic::catch_unwind(|| {
-                            let mut s = EDITOR_STATE.write();
-                            s.dragging_id = None;
-                            s.just_dragged = true;
+                            EDITOR_STATE.write().end_drag();
                         });
                     }) as Box<dyn FnMut()>);
                     let _ = window_retry.set_timeout_with_callback_and_timeout_and_arguments_0(retry.as_ref().unchecked_ref(), 0);
@@ -749,79 +2365,295 @@ fn stop_dragging() {
 
     #[cfg(not(target_arch = "wasm32"))]
     {
-        let mut state = EDITOR_STATE.write();
-        state.dragging_id = None;
-        state.just_dragged = true;
+        EDITOR_STATE.write().end_drag();
     }
 }
 
 fn delete_component(id: usize) {
-    let mut state = EDITOR_STATE.write();
-    
-    for component in state.components.values_mut() {
-        component.children.retain(|&child_id| child_id != id);
-    }
-    
-    state.components.remove(&id);
-    
-    if state.selected_id == Some(id) {
-        state.selected_id = None;
+    if EDITOR_STATE.read().components.get(&id).is_none_or(|c| c.locked) {
+        return;
     }
+    push_history();
+    EDITOR_STATE.write().document_mut().remove(id);
+    state_integrity_check();
 }
 
 fn update_content(component_id: usize, content: String) {
     let mut state = EDITOR_STATE.write();
+    state.mark_changed();
     if let Some(component) = state.components.get_mut(&component_id) {
         component.content = content;
+        component.touch();
+    }
+    sync_instances(&mut state, component_id);
+}
+
+// Map a 0-100% slider value onto the `opacity` style; fully opaque removes the property
+fn set_opacity(component_id: usize, percent: f64) {
+    let percent = percent.clamp(0.0, 100.0).round();
+    let value = if percent >= 100.0 { String::new() } else { format!("{}", percent / 100.0) };
+    styles_editor::update_style(component_id, "opacity", value);
+}
+
+// One-click effects in the properties panel: (button label, style property, value)
+const STYLE_PRESETS: [(&str, &str, &str); 3] = [
+    ("Rounded", "border-radius", "8px"),
+    ("Shadow", "box-shadow", "0 2px 8px rgba(0,0,0,0.25)"),
+    ("Bordered", "border", "1px solid #ccc"),
+];
+
+// Set a preset style, or remove the property when the component already has it (undoable)
+fn toggle_style_preset(component_id: usize, property: &str, value: &str) {
+    let has_property = EDITOR_STATE.read().components.get(&component_id).is_some_and(|c| c.styles.contains_key(property));
+    push_history();
+    let value = if has_property { String::new() } else { value.to_string() };
+    styles_editor::update_style(component_id, property, value);
+}
+
+// Step of the container gap buttons, in px
+const GAP_STEP: f64 = 4.0;
+
+// Direction of a container laid out as a flex box (row when only `display: flex` is set); None otherwise
+fn flex_direction(component: &Component) -> Option<&str> {
+    if component.styles.get("display").map(|d| d.trim()) != Some("flex") {
+        return None;
+    }
+    Some(component.styles.get("flex-direction").map(|d| d.trim()).unwrap_or("row"))
+}
+
+// Spacing between a container's children in px, from its `gap` style (exported as is); None when unset,
+// zero or not a plain px value. The tidy layout spaces the container's children on the canvas by it too.
+pub fn child_gap(component: &Component) -> Option<f64> {
+    component.styles.get("gap")
+        .and_then(|g| g.trim().trim_end_matches("px").trim().parse().ok())
+        .filter(|&g: &f64| g > 0.0)
+}
+
+// Lay a container out as a flex column or row (undoable); the gap is kept
+fn set_flex_direction(component_id: usize, direction: &str) {
+    push_history();
+    styles_editor::update_style(component_id, "display", "flex".to_string());
+    styles_editor::update_style(component_id, "flex-direction", direction.to_string());
+}
+
+// The px gap the stepper works from: 0 when unset, None for a gap in other units (1rem, 5%, calc(...))
+// which the stepper leaves alone rather than overwrite
+fn stepper_gap(component: &Component) -> Option<f64> {
+    match component.styles.get("gap").map(|g| g.trim()).filter(|g| !g.is_empty()) {
+        None => Some(0.0),
+        Some(gap) => gap.trim_end_matches("px").trim().parse().ok(),
     }
 }
 
+// Grow or shrink a container's px gap (undoable). A container that isn't a flex box yet becomes a column,
+// so the gap has something to act on.
+fn step_child_gap(component_id: usize, delta: f64) {
+    let Some((gap, is_flex)) = EDITOR_STATE.read().components.get(&component_id)
+        .and_then(|c| Some((stepper_gap(c)?, flex_direction(c).is_some()))) else { return };
+    push_history();
+    if !is_flex {
+        styles_editor::update_style(component_id, "display", "flex".to_string());
+        styles_editor::update_style(component_id, "flex-direction", "column".to_string());
+    }
+    let gap = (gap + delta).max(0.0);
+    styles_editor::update_style(component_id, "gap", if gap == 0.0 { String::new() } else { format!("{}px", gap) });
+}
+
+// Rotation in degrees from a `rotate(Ndeg)` in the `transform` style (0 when absent)
+pub fn rotation_deg(styles: &HashMap<String, String>) -> f64 {
+    styles.get("transform")
+        .and_then(|t| {
+            let start = t.find("rotate(")? + "rotate(".len();
+            let end = start + t[start..].find("deg)")?;
+            t[start..end].trim().parse().ok()
+        })
+        .unwrap_or(0.0)
+}
+
+// Write `rotate(Ndeg)` into the `transform` style, keeping any other transform functions
+fn set_rotation(component_id: usize, degrees: f64) {
+    let current = EDITOR_STATE.read().components.get(&component_id).and_then(|c| c.styles.get("transform").cloned()).unwrap_or_default();
+
+    // drop the existing rotate(...) segment
+    let mut others = current.clone();
+    if let Some(start) = current.find("rotate(") {
+        if let Some(len) = current[start..].find(')') {
+            others = format!("{}{}", &current[..start], &current[start + len + 1..]);
+        }
+    }
+    let others = others.split_whitespace().collect::<Vec<_>>().join(" ");
+
+    let value = match (degrees.round() as i64, others.is_empty()) {
+        (0, _) => others,
+        (deg, true) => format!("rotate({}deg)", deg),
+        (deg, false) => format!("{} rotate({}deg)", others, deg),
+    };
+    styles_editor::update_style(component_id, "transform", value);
+}
+
+// Empty text removes the name. Called per keystroke, so callers record the undo step (push_history_once).
+pub fn rename_component(component_id: usize, name: String) {
+    let mut state = EDITOR_STATE.write();
+    state.mark_changed();
+    if let Some(component) = state.components.get_mut(&component_id) {
+        component.name = if name.trim().is_empty() { None } else { Some(name) };
+        component.touch();
+    }
+}
+
+// Empty text removes the note
+fn update_note(component_id: usize, note: String) {
+    let mut state = EDITOR_STATE.write();
+    state.mark_changed();
+    if let Some(component) = state.components.get_mut(&component_id) {
+        component.note = if note.trim().is_empty() { None } else { Some(note) };
+        component.touch();
+    }
+}
+
+// "text" is the default and is stored as None
+fn set_input_type(component_id: usize, input_type: String) {
+    let mut state = EDITOR_STATE.write();
+    state.mark_changed();
+    if let Some(component) = state.components.get_mut(&component_id) {
+        component.input_type = if input_type == "text" { None } else { Some(input_type) };
+        component.touch();
+    }
+    sync_instances(&mut state, component_id);
+}
+
+// Spacer height in px for the properties panel; empty when the style isn't a plain px (or unitless) value
+fn spacer_height(component: &Component) -> String {
+    component.styles.get("height")
+        .map(|h| h.trim().trim_end_matches("px").trim())
+        .filter(|h| h.parse::<f64>().is_ok())
+        .unwrap_or_default()
+        .to_string()
+}
+
+// Write the spacer height into its styles; an empty field removes it
+fn set_spacer_height(component_id: usize, value: &str) {
+    let value = value.trim();
+    if value.is_empty() {
+        update_style(component_id, "height", String::new());
+    } else if let Ok(height) = value.parse::<f64>() {
+        update_style(component_id, "height", format!("{}px", height.max(0.0)));
+    }
+}
+
+// Switch a component between flow and relative positioning (undoable)
+fn set_relative_position(component_id: usize, relative: bool) {
+    push_history();
+    if let Some(component) = EDITOR_STATE.write().components.get_mut(&component_id) {
+        component.relative_position = relative;
+        component.touch();
+    }
+}
+
+// `left`/`top` of a relatively positioned component in px: its canvas offset from its parent's box, or from
+// the canvas origin for a top-level component. None when it keeps the default flow positioning.
+pub fn position_offset(state: &EditorState, component_id: usize) -> Option<(f64, f64)> {
+    let component = state.components.get(&component_id).filter(|c| c.relative_position)?;
+    let (origin_x, origin_y) = state.document().parent_of(component_id)
+        .and_then(|parent| state.components.get(&parent))
+        .map(|parent| (parent.x, parent.y))
+        .unwrap_or((0.0, 0.0));
+    Some(((component.x - origin_x).round(), (component.y - origin_y).round()))
+}
+
+// Declarations that apply position_offset, merged over the component's own styles
+pub fn position_styles(offset: (f64, f64)) -> [(&'static str, String); 3] {
+    [("position", "relative".to_string()), ("left", format!("{}px", offset.0)), ("top", format!("{}px", offset.1))]
+}
+
+fn set_expand_content(component_id: usize, expand: bool) {
+    let mut state = EDITOR_STATE.write();
+    state.mark_changed();
+    if let Some(component) = state.components.get_mut(&component_id) {
+        component.expand_content = expand;
+        component.touch();
+    }
+}
+
+// Empty text removes the cite attribute
+fn update_cite(component_id: usize, cite: String) {
+    let mut state = EDITOR_STATE.write();
+    state.mark_changed();
+    if let Some(component) = state.components.get_mut(&component_id) {
+        component.cite = if cite.trim().is_empty() { None } else { Some(cite) };
+        component.touch();
+    }
+    sync_instances(&mut state, component_id);
+}
+
+// Write one style property into every selected component as a single undoable step
+fn apply_style_to_selection(property: &str, value: String) {
+    let ids = EDITOR_STATE.read().selected_ids.clone();
+    push_history();
+    for id in ids {
+        update_style(id, property, value.clone());
+    }
+    // pending edits in the styles editor would otherwise overwrite the bulk change on save
+    STYLE_EDIT_BUFFER.write().clear();
+}
+
 fn update_style<A>(component_id: usize, property: A, value: String) where A: Into<String> {
     let property = property.into();
     let mut state = EDITOR_STATE.write();
+    state.mark_changed();
+    let previous = state.components.get(&component_id).map(|c| c.styles.clone()).unwrap_or_default();
     if let Some(component) = state.components.get_mut(&component_id) {
         if value.is_empty() {
             component.styles.remove(&property);
         } else {
             component.styles.insert(property, value);
         }
+        component.touch();
     }
+    remember_styles(&state, component_id, &previous);
+    sync_instances(&mut state, component_id);
 }
 
 // Add a child by id (used when completing a manual connection)
 fn complete_connection(from_id: usize, to_id: usize) {
-    let mut state = EDITOR_STATE.write();
-    if let Some(from) = state.components.get_mut(&from_id) {
-        if from.component_type != ComponentType::Container {
-            return; // only containers can have children
-        }
-        if !from.children.contains(&to_id) && to_id != from_id {
-            from.children.push(to_id);
-            state.selected_id = Some(to_id);
+    // a dragged arrowhead moves its connection instead of adding one
+    let retargeting = EDITOR_STATE.read().retargeting_edge.filter(|&(parent, _)| parent == from_id);
+    let result = match retargeting {
+        Some((_, old_child)) => retarget_connection(from_id, old_child, to_id),
+        None => connect_components(from_id, to_id),
+    };
+    match result {
+        Ok(()) => {
+            let mut state = EDITOR_STATE.write();
+            state.select(Some(to_id));
+            state.connect_error = None;
 
-            #[cfg(target_arch = "wasm32")]
-            {
-                web_sys::console::log_1(&format!("complete_connection: {} -> {}", from_id, to_id).into());
-            }
+            debug_log(|| format!("complete_connection: {} -> {}", from_id, to_id));
         }
-    }
-}
-
-fn add_child_to_container(container_id: usize) {
-    let mut state = EDITOR_STATE.write();
-    
-    if let Some(&available_id) = state.components.keys().find(|&&id| 
-            id != container_id && !state.components.get(&container_id).unwrap().children.contains(&id)) {
-        if let Some(container) = state.components.get_mut(&container_id) {
-            container.children.push(available_id);
+        Err(e) => {
+            debug_log(|| format!("complete_connection: {} -> {} rejected: {}", from_id, to_id, e));
+            // shown on the canvas so the user knows why nothing happened
+            EDITOR_STATE.write().connect_error = Some(format!("Can't connect #{} -> #{}: {}", from_id, to_id, e));
         }
     }
 }
 
-fn set_mode(mode: EditorMode) {
+pub fn set_mode(mode: EditorMode) {
     EDITOR_STATE.write().mode = mode;
 }
 
+// Element id of the canvas column
+pub const CANVAS_ID: &str = "canvas";
+
+// Grid spacing from the toolbox input; anything that isn't a number is ignored
+fn set_grid_size(value: &str) {
+    if let Ok(size) = value.trim().parse::<f64>() {
+        if size.is_finite() {
+            EDITOR_STATE.write().grid_size = size.clamp(MIN_GRID_SIZE, MAX_GRID_SIZE);
+        }
+    }
+}
+
 fn set_hovering_container(id: Option<usize>) {
     EDITOR_STATE.write().hovering_container_id = id;
 }
@@ -832,30 +2664,47 @@ fn set_connecting_hover_target(id: Option<usize>) {
 
 fn start_connecting(id: usize) {
     // Read component coordinates first under a read lock to avoid overlapping borrows
-    let (comp_x, comp_y) = {
+    let (comp_x, comp_y, comp_w, comp_h) = {
         let state_read = EDITOR_STATE.read();
         if let Some(comp) = state_read.components.get(&id) {
-            (comp.x, comp.y)
+            (comp.x, comp.y, comp.width, comp.height)
         } else {
-            (0.0, 0.0)
+            (0.0, 0.0, BOX_WIDTH, DEFAULT_BOX_HEIGHT)
         }
     };
 
     let mut state = EDITOR_STATE.write();
+    state.connect_error = None;
+    // connecting and dragging are exclusive; drop any drag the button press may have started
+    state.dragging_id = None;
+    state.just_dragged = false;
     state.connecting_from = Some(id);
-    state.connecting_mouse_x = comp_x + 100.0;
-    state.connecting_mouse_y = comp_y + 40.0;
+    state.connecting_mouse_x = comp_x + comp_w / 2.0;
+    state.connecting_mouse_y = comp_y + comp_h / 2.0;
+}
+
+// Start dragging the head of the parent -> child arrow; dropping it on a component retargets the connection
+fn start_retargeting(edge: (usize, usize)) {
+    start_connecting(edge.0);
+    let mut state = EDITOR_STATE.write();
+    state.retargeting_edge = Some(edge);
+    state.selected_edge = Some(edge);
 }
 
+// Leave connecting mode, whether the connection completed or was aborted, so the next press drags normally
 fn stop_connecting() {
     let mut state = EDITOR_STATE.write();
     state.connecting_from = None;
+    state.retargeting_edge = None;
     state.connecting_hover_target_id = None;
+    state.dragging_id = None;
+    state.just_dragged = false;
 }
 
 // Calculate the point on the perimeter of an axis-aligned rectangle (rect_x, rect_y, rect_w, rect_h)
 // that lies on the line from the rect's center toward (source_x, source_y).
-fn rect_edge_point_towards(source_x: f64, source_y: f64, rect_x: f64, rect_y: f64, rect_w: f64, rect_h: f64) -> (f64, f64) {
+// Rotation (`transform: rotate`) is ignored, so arrows to rotated boxes only approximately touch their edges.
+pub fn rect_edge_point_towards(source_x: f64, source_y: f64, rect_x: f64, rect_y: f64, rect_w: f64, rect_h: f64) -> (f64, f64) {
     let cx = rect_x + rect_w / 2.0;
     let cy = rect_y + rect_h / 2.0;
     let vx = source_x - cx;
@@ -876,26 +2725,3 @@ fn rect_edge_point_towards(source_x: f64, source_y: f64, rect_x: f64, rect_y: f6
 
     (cx + vx * s, cy + vy * s)
 }
-
-fn schedule_task<F: 'static + FnOnce()>(f: F) {
-    #[cfg(target_arch = "wasm32")]
-    {
-        use wasm_bindgen::JsCast;
-        if let Some(window) = web_sys::window() {
-            let mut opt = Some(f);
-            let closure = wasm_bindgen::prelude::Closure::wrap(Box::new(move || {
-                if let Some(func) = opt.take() {
-                    func();
-                }
-            }) as Box<dyn FnMut()>);
-            let _ = window.set_timeout_with_callback_and_timeout_and_arguments_0(closure.as_ref().unchecked_ref(), 0);
-            closure.forget();
-        }
-    }
-
-    #[cfg(not(target_arch = "wasm32"))]
-    {
-        // non-web targets: run immediately
-        f();
-    }
-}
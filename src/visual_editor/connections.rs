@@ -0,0 +1,315 @@
+use dioxus::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::fmt;
+use std::rc::Rc;
+use super::component::{ComponentType, EditorState, EDITOR_STATE};
+use super::history::push_history;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ConnectError {
+    // No component with this id
+    NotFound(usize),
+    // The parent isn't a container, so it can't have children
+    NotContainer(usize),
+    // A component can't be its own child
+    SelfLink,
+    // The parent is already a descendant of the child
+    Cycle,
+    // The child already belongs to this other container (components have a single parent)
+    AlreadyHasParent(usize),
+    // The embedding app's child policy rejects this (parent type, child type) pair
+    NotAllowed(ComponentType, ComponentType),
+}
+
+impl fmt::Display for ConnectError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConnectError::NotFound(id) => write!(f, "component #{} does not exist", id),
+            ConnectError::NotContainer(id) => write!(f, "component #{} is not a container", id),
+            ConnectError::SelfLink => write!(f, "a component cannot be connected to itself"),
+            ConnectError::Cycle => write!(f, "connection would create a cycle"),
+            ConnectError::AlreadyHasParent(id) => write!(f, "component is already a child of #{}", id),
+            ConnectError::NotAllowed(parent, child) => write!(f, "a {} can't be placed in a {}", child.label(), parent.label()),
+        }
+    }
+}
+
+impl std::error::Error for ConnectError {}
+
+// How a connection arrow is drawn; connections without an entry use the default
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct EdgeStyle {
+    pub color: String,
+    pub dashed: bool,
+}
+
+impl Default for EdgeStyle {
+    fn default() -> Self {
+        Self { color: "#666666".to_string(), dashed: false }
+    }
+}
+
+pub fn edge_style(state: &EditorState, parent: usize, child: usize) -> EdgeStyle {
+    state.edge_styles.get(&(parent, child)).cloned().unwrap_or_default()
+}
+
+// Restyle the parent -> child arrow as one undoable step; the default style removes the entry
+pub fn set_edge_style(parent: usize, child: usize, style: EdgeStyle) {
+    push_history();
+    let mut state = EDITOR_STATE.write();
+    if style == EdgeStyle::default() {
+        state.edge_styles.remove(&(parent, child));
+    } else {
+        state.edge_styles.insert((parent, child), style);
+    }
+}
+
+type ChildPolicy = Rc<dyn Fn(&ComponentType, &ComponentType) -> bool>;
+
+// Plain thread-local like the renderer registry, so a policy can be set before the dioxus runtime exists
+thread_local! {
+    static CHILD_POLICY: RefCell<Option<ChildPolicy>> = const { RefCell::new(None) };
+}
+
+// Restrict which component types may go inside which containers, e.g. to forbid forms inside forms.
+// The policy gets (parent type, child type) and only ever sees container parents. Without one every
+// combination is allowed. Call before mounting `VisualEditor`; existing connections aren't re-checked:
+//
+//     set_child_policy(|parent, child| !(*parent == ComponentType::Form && *child == ComponentType::Form));
+// Embedding API: the app itself keeps the permissive default
+#[allow(dead_code)]
+pub fn set_child_policy(policy: impl Fn(&ComponentType, &ComponentType) -> bool + 'static) {
+    CHILD_POLICY.with(|p| *p.borrow_mut() = Some(Rc::new(policy)));
+}
+
+// Whether the child policy lets a `child_type` component go inside a `parent_type` container
+pub fn can_be_child(parent_type: &ComponentType, child_type: &ComponentType) -> bool {
+    CHILD_POLICY
+        .with(|p| p.borrow().clone())
+        .is_none_or(|policy| policy(parent_type, child_type))
+}
+
+// Validate a parent -> child connection against the current state without changing it.
+// All connection rules live here; an existing identical connection is allowed (connecting it again is a no-op).
+pub fn can_connect(state: &EditorState, parent: usize, child: usize) -> Result<(), ConnectError> {
+    let parent_comp = state.components.get(&parent).ok_or(ConnectError::NotFound(parent))?;
+    if !state.components.contains_key(&child) {
+        return Err(ConnectError::NotFound(child));
+    }
+    if parent == child {
+        return Err(ConnectError::SelfLink);
+    }
+    if !parent_comp.component_type.is_container() {
+        return Err(ConnectError::NotContainer(parent));
+    }
+    let child_type = &state.components[&child].component_type;
+    if !can_be_child(&parent_comp.component_type, child_type) {
+        return Err(ConnectError::NotAllowed(parent_comp.component_type.clone(), child_type.clone()));
+    }
+    if state.document().is_descendant(child, parent) {
+        return Err(ConnectError::Cycle);
+    }
+    let other_parent = state.components.values()
+        .filter(|comp| comp.id != parent && comp.children.contains(&child))
+        .map(|comp| comp.id)
+        .min();
+    if let Some(other) = other_parent {
+        return Err(ConnectError::AlreadyHasParent(other));
+    }
+    Ok(())
+}
+
+// Make `child` a child of `parent` as one undoable step; connecting an existing pair is a no-op
+pub fn connect_components(parent: usize, child: usize) -> Result<(), ConnectError> {
+    let already_connected = {
+        let state = EDITOR_STATE.read();
+        can_connect(&state, parent, child)?;
+        state.components[&parent].children.contains(&child)
+    };
+    if already_connected {
+        return Ok(());
+    }
+
+    push_history();
+    EDITOR_STATE.write().document_mut().connect(parent, child)
+}
+
+// Remove `child` from `parent`'s children as one undoable step
+pub fn disconnect_components(parent: usize, child: usize) -> Result<(), ConnectError> {
+    {
+        let state = EDITOR_STATE.read();
+        let parent_comp = state.components.get(&parent).ok_or(ConnectError::NotFound(parent))?;
+        if !parent_comp.children.contains(&child) {
+            return Err(ConnectError::NotFound(child));
+        }
+    }
+
+    push_history();
+    EDITOR_STATE.write().document_mut().disconnect(parent, child)
+}
+
+// Point the parent -> old_child connection at new_child instead, as one undoable step. new_child takes
+// old_child's place in the children order and the arrow keeps its style.
+pub fn retarget_connection(parent: usize, old_child: usize, new_child: usize) -> Result<(), ConnectError> {
+    if old_child == new_child {
+        return Ok(());
+    }
+    {
+        let state = EDITOR_STATE.read();
+        let parent_comp = state.components.get(&parent).ok_or(ConnectError::NotFound(parent))?;
+        if !parent_comp.children.contains(&old_child) {
+            return Err(ConnectError::NotFound(old_child));
+        }
+        can_connect(&state, parent, new_child)?;
+    }
+
+    push_history();
+    EDITOR_STATE.write().document_mut().replace_child(parent, old_child, new_child)
+}
+
+// Like can_connect, but the child may already belong to another container (moving takes it out)
+pub fn can_move(state: &EditorState, parent: usize, child: usize) -> Result<(), ConnectError> {
+    match can_connect(state, parent, child) {
+        Err(ConnectError::AlreadyHasParent(_)) => Ok(()),
+        result => result,
+    }
+}
+
+// Move `child` into `parent` at `index` of its children, or to the top level when `parent` is None,
+// as one undoable step. The child leaves every container it was in; reordering within the same
+// parent keeps the arrow's style.
+pub fn move_component(child: usize, parent: Option<usize>, index: usize) -> Result<(), ConnectError> {
+    {
+        let state = EDITOR_STATE.read();
+        if !state.components.contains_key(&child) {
+            return Err(ConnectError::NotFound(child));
+        }
+        if let Some(parent) = parent {
+            can_move(&state, parent, child)?;
+        }
+    }
+
+    push_history();
+    EDITOR_STATE.write().document_mut().move_to(child, parent, index)
+}
+
+// Where `id` sits: its parent (None at the top level), its siblings in order and its index among them
+fn sibling_position(state: &EditorState, id: usize) -> Option<(Option<usize>, Vec<usize>, usize)> {
+    let parent = state.document().parent_of(id);
+    let siblings = match parent {
+        Some(parent) => state.document().children_of(parent).to_vec(),
+        None => state.document().roots(),
+    };
+    let index = siblings.iter().position(|&s| s == id)?;
+    Some((parent, siblings, index))
+}
+
+// Swap `id` with the sibling before (`up`) or after it. Top-level order follows ids, so only children move.
+pub fn move_among_siblings(id: usize, up: bool) -> Result<bool, ConnectError> {
+    let Some((Some(parent), siblings, index)) = sibling_position(&EDITOR_STATE.read(), id) else { return Ok(false) };
+    if (up && index == 0) || (!up && index + 1 >= siblings.len()) {
+        return Ok(false);
+    }
+    // move_component counts the child's old slot, so moving down has to skip past the next sibling
+    move_component(id, Some(parent), if up { index - 1 } else { index + 2 })?;
+    Ok(true)
+}
+
+// Move `id` out of its container to just after it in the grandparent (or to the top level)
+pub fn move_out_of_parent(id: usize) -> Result<bool, ConnectError> {
+    let destination = {
+        let state = EDITOR_STATE.read();
+        let Some(parent) = state.document().parent_of(id) else { return Ok(false) };
+        match sibling_position(&state, parent) {
+            Some((grandparent, _, index)) => (grandparent, index + 1),
+            None => (None, 0),
+        }
+    };
+    move_component(id, destination.0, destination.1)?;
+    Ok(true)
+}
+
+// Move `id` into the nearest container among its siblings: to the end of the one before it, otherwise to
+// the start of the one after it
+pub fn move_into_sibling(id: usize) -> Result<bool, ConnectError> {
+    let destination = {
+        let state = EDITOR_STATE.read();
+        let Some((_, siblings, index)) = sibling_position(&state, id) else { return Ok(false) };
+        let is_target = |s: &usize| state.components.get(s).is_some_and(|c| c.component_type.is_container()) && can_move(&state, *s, id).is_ok();
+        let before = siblings[..index].iter().rev().find(|s| is_target(s));
+        let after = siblings[index + 1..].iter().find(|s| is_target(s));
+        match (before, after) {
+            (Some(&before), _) => (before, state.components[&before].children.len()),
+            (None, Some(&after)) => (after, 0),
+            (None, None) => return Ok(false),
+        }
+    };
+    move_component(id, Some(destination.0), destination.1)?;
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::visual_editor::component::Component;
+
+    // #0 Container > #1 Container > #2 Heading; #3 Container and #4 Form on their own
+    fn sample() -> EditorState {
+        let mut state = EditorState::default();
+        for component_type in [ComponentType::Container, ComponentType::Container, ComponentType::Heading,
+            ComponentType::Container, ComponentType::Form] {
+            state.document_mut().add(Component::new(0, component_type, 0.0, 0.0));
+        }
+        state.components.get_mut(&0).unwrap().children = vec![1];
+        state.components.get_mut(&1).unwrap().children = vec![2];
+        state
+    }
+
+    #[test]
+    fn unknown_parent_or_child_is_not_found() {
+        let state = sample();
+        assert_eq!(can_connect(&state, 9, 2), Err(ConnectError::NotFound(9)));
+        assert_eq!(can_connect(&state, 3, 9), Err(ConnectError::NotFound(9)));
+    }
+
+    #[test]
+    fn component_cannot_be_its_own_child() {
+        let state = sample();
+        assert_eq!(can_connect(&state, 3, 3), Err(ConnectError::SelfLink));
+    }
+
+    #[test]
+    fn ancestor_cannot_become_a_child() {
+        let state = sample();
+        assert_eq!(can_connect(&state, 1, 0), Err(ConnectError::Cycle));
+        // through more than one level too
+        let mut deeper = sample();
+        deeper.components.get_mut(&2).unwrap().component_type = ComponentType::Container;
+        assert_eq!(can_connect(&deeper, 2, 0), Err(ConnectError::Cycle));
+    }
+
+    #[test]
+    fn only_containers_take_children() {
+        let state = sample();
+        assert_eq!(can_connect(&state, 2, 3), Err(ConnectError::NotContainer(2)));
+    }
+
+    #[test]
+    fn child_policy_can_forbid_pairs() {
+        let state = sample();
+        set_child_policy(|parent, child| !(*parent == ComponentType::Container && *child == ComponentType::Form));
+        assert_eq!(can_connect(&state, 3, 4),
+            Err(ConnectError::NotAllowed(ComponentType::Container, ComponentType::Form)));
+        assert_eq!(can_connect(&state, 4, 3), Ok(()));
+    }
+
+    #[test]
+    fn child_with_a_parent_cannot_get_a_second_one() {
+        let state = sample();
+        assert_eq!(can_connect(&state, 3, 2), Err(ConnectError::AlreadyHasParent(1)));
+        // moving is allowed, and so is the existing connection
+        assert_eq!(can_move(&state, 3, 2), Ok(()));
+        assert_eq!(can_connect(&state, 1, 2), Ok(()));
+    }
+}
@@ -0,0 +1,177 @@
+use dioxus::prelude::*;
+use super::component::{ComponentType, EditorState, EDITOR_STATE};
+
+// WCAG AA minimum for normal-size text
+pub const MIN_CONTRAST: f64 = 4.5;
+
+// An opaque sRGB color with 0-255 channels
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Rgb(pub u8, pub u8, pub u8);
+
+// Parse `#rgb`, `#rrggbb` (also with an alpha digit/pair), `rgb(r, g, b)` / `rgba(r, g, b, a)` and a few
+// common names. Anything else, such as gradients or `var(...)`, gives None, and so does a translucent color:
+// what shows through it isn't known, so it can't be compared.
+pub fn parse_color(value: &str) -> Option<Rgb> {
+    parse_color_with_alpha(value).filter(|&(_, alpha)| alpha >= 1.0).map(|(color, _)| color)
+}
+
+// Whether parse_color understands the value's format, translucent or not
+pub fn is_color(value: &str) -> bool {
+    parse_color_with_alpha(value).is_some()
+}
+
+// The color and its alpha (0 to 1)
+fn parse_color_with_alpha(value: &str) -> Option<(Rgb, f64)> {
+    let value = value.trim().to_ascii_lowercase();
+    if let Some(hex) = value.strip_prefix('#') {
+        // also keeps the byte slicing below on char boundaries
+        if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return None;
+        }
+        let channel = |s: &str| u8::from_str_radix(s, 16).ok();
+        return match hex.len() {
+            3 | 4 => {
+                let digit = |i: usize| channel(&hex[i..i + 1]).map(|d| d * 17);
+                let alpha = if hex.len() == 4 { digit(3)? } else { 255 };
+                Some((Rgb(digit(0)?, digit(1)?, digit(2)?), alpha as f64 / 255.0))
+            }
+            6 | 8 => {
+                let pair = |i: usize| channel(&hex[i..i + 2]);
+                let alpha = if hex.len() == 8 { pair(6)? } else { 255 };
+                Some((Rgb(pair(0)?, pair(2)?, pair(4)?), alpha as f64 / 255.0))
+            }
+            _ => None,
+        };
+    }
+    if let Some(args) = value.strip_prefix("rgba(").or_else(|| value.strip_prefix("rgb(")).and_then(|v| v.strip_suffix(')')) {
+        let parts = args.split([',', ' ', '/']).filter(|p| !p.is_empty()).collect::<Vec<_>>();
+        if parts.len() < 3 || parts.len() > 4 {
+            return None;
+        }
+        let alpha = match parts.get(3) {
+            Some(alpha) => match alpha.strip_suffix('%') {
+                Some(percent) => percent.parse::<f64>().ok()? / 100.0,
+                None => alpha.parse::<f64>().ok()?,
+            },
+            None => 1.0,
+        };
+        let channel = |s: &str| s.parse::<f64>().ok().map(|v| v.clamp(0.0, 255.0).round() as u8);
+        return Some((Rgb(channel(parts[0])?, channel(parts[1])?, channel(parts[2])?), alpha.clamp(0.0, 1.0)));
+    }
+    let color = match value.as_str() {
+        "white" => Rgb(255, 255, 255),
+        "black" => Rgb(0, 0, 0),
+        "red" => Rgb(255, 0, 0),
+        "green" => Rgb(0, 128, 0),
+        "blue" => Rgb(0, 0, 255),
+        "gray" | "grey" => Rgb(128, 128, 128),
+        _ => return None,
+    };
+    Some((color, 1.0))
+}
+
+// WCAG relative luminance
+fn luminance(color: Rgb) -> f64 {
+    let linear = |c: u8| {
+        let c = c as f64 / 255.0;
+        if c <= 0.03928 { c / 12.92 } else { ((c + 0.055) / 1.055).powf(2.4) }
+    };
+    0.2126 * linear(color.0) + 0.7152 * linear(color.1) + 0.0722 * linear(color.2)
+}
+
+// WCAG contrast ratio between two colors, from 1 (identical) to 21 (black on white)
+pub fn contrast_ratio(fg: Rgb, bg: Rgb) -> f64 {
+    let (a, b) = (luminance(fg), luminance(bg));
+    (a.max(b) + 0.05) / (a.min(b) + 0.05)
+}
+
+// Text and background color the component ends up with in the preview: its own styles, then its
+// containers' (nearest first), then the page's, then the preview defaults. None when either deciding value
+// isn't a plain color (e.g. a gradient background).
+pub fn effective_colors(state: &EditorState, id: usize) -> Option<(Rgb, Rgb)> {
+    let mut chain = vec![&state.components.get(&id)?.styles];
+    chain.extend(state.document().ancestors(id).iter().rev().filter_map(|a| state.components.get(a)).map(|c| &c.styles));
+    chain.push(&state.page_styles);
+
+    let fg = chain.iter().find_map(|s| s.get("color")).map_or(Some(Rgb(0, 0, 0)), |v| parse_color(v))?;
+    let bg = chain.iter()
+        .find_map(|s| s.get("background-color").or_else(|| s.get("background")))
+        .map_or(parse_color(&state.preview_background), |v| parse_color(v))?;
+    Some((fg, bg))
+}
+
+// Pass/fail badge for text components in the properties panel
+#[component]
+pub fn ContrastBadge(component_id: usize) -> Element {
+    let state = EDITOR_STATE.read();
+    let is_text = state.components.get(&component_id)
+        .is_some_and(|c| matches!(c.component_type, ComponentType::Heading | ComponentType::Paragraph | ComponentType::Quote));
+    if !is_text {
+        return rsx! {};
+    }
+    let Some((fg, bg)) = effective_colors(&state, component_id) else {
+        return rsx! {
+            p { style: "margin: 0 12px 12px 12px; font-size: 12px; color: #666;", "Contrast: can't tell (text or background isn't a plain color)" }
+        };
+    };
+    let ratio = contrast_ratio(fg, bg);
+    let passes = ratio >= MIN_CONTRAST;
+    let (color, verdict) = if passes { ("#2e7d32", "passes AA") } else { ("#c62828", "below 4.5:1") };
+
+    rsx! {
+        p { style: "margin: 0 12px 12px 12px; font-size: 12px;",
+            span {
+                style: "display: inline-block; padding: 2px 6px; border-radius: 4px; color: white; background: {color};",
+                title: "WCAG contrast between the text color and the background behind it",
+                "Contrast {ratio:.1}:1 — {verdict}"
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hex_colors_of_every_length_parse() {
+        assert_eq!(parse_color("#fff"), Some(Rgb(255, 255, 255)));
+        assert_eq!(parse_color("#0F0f"), Some(Rgb(0, 255, 0)));
+        assert_eq!(parse_color(" #123456 "), Some(Rgb(0x12, 0x34, 0x56)));
+        assert_eq!(parse_color("#123456ff"), Some(Rgb(0x12, 0x34, 0x56)));
+        assert_eq!(parse_color("#12345"), None);
+        assert_eq!(parse_color("#ggg"), None);
+    }
+
+    #[test]
+    fn rgb_functions_and_names_parse() {
+        assert_eq!(parse_color("rgb(10, 20, 30)"), Some(Rgb(10, 20, 30)));
+        assert_eq!(parse_color("rgb(10 20 30 / 100%)"), Some(Rgb(10, 20, 30)));
+        assert_eq!(parse_color("rgba(300, 0, 0, 1)"), Some(Rgb(255, 0, 0)));
+        assert_eq!(parse_color("Black"), Some(Rgb(0, 0, 0)));
+        assert_eq!(parse_color("grey"), Some(Rgb(128, 128, 128)));
+        assert_eq!(parse_color("rgb(1, 2)"), None);
+        assert_eq!(parse_color("linear-gradient(red, blue)"), None);
+    }
+
+    #[test]
+    fn translucent_colors_are_not_compared() {
+        assert_eq!(parse_color("rgba(0, 0, 0, 0)"), None);
+        assert_eq!(parse_color("rgba(0, 0, 0, 0.5)"), None);
+        assert_eq!(parse_color("rgb(0 0 0 / 50%)"), None);
+        assert_eq!(parse_color("#0008"), None);
+        assert_eq!(parse_color("#00000080"), None);
+        // still a color, just not one the contrast check can use
+        assert!(is_color("rgba(0, 0, 0, 0.5)"));
+        assert!(!is_color("var(--accent)"));
+    }
+
+    #[test]
+    fn black_on_white_is_the_maximum_ratio() {
+        let (black, white) = (Rgb(0, 0, 0), Rgb(255, 255, 255));
+        assert!((contrast_ratio(black, white) - 21.0).abs() < 1e-9);
+        assert_eq!(contrast_ratio(white, black), contrast_ratio(black, white));
+        assert!((contrast_ratio(white, white) - 1.0).abs() < 1e-9);
+        assert!(contrast_ratio(Rgb(119, 119, 119), white) < MIN_CONTRAST);
+    }
+}
@@ -0,0 +1,169 @@
+use dioxus::prelude::*;
+use serde::{Deserialize, Serialize};
+use super::component::EDITOR_STATE;
+use super::history::push_history;
+
+// Starting point for the reset: border-box sizing and no browser default margins
+pub const DEFAULT_CSS_RESET: &str = "*, *::before, *::after { box-sizing: border-box; }\n\
+body, h1, p, blockquote, form, hr { margin: 0; }\n\
+img { max-width: 100%; display: block; }";
+
+// Stylesheet put in front of the page's own CSS in the preview and the export; saved with the project
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CssReset {
+    pub enabled: bool,
+    pub css: String,
+}
+
+impl Default for CssReset {
+    fn default() -> Self {
+        CssReset { enabled: false, css: DEFAULT_CSS_RESET.to_string() }
+    }
+}
+
+// Rewrite `css` so it only applies inside `scope`: each selector is prefixed with it, and a leading `html`/`body`
+// stands for the scope element itself (`body > p` becomes `<scope> > p`). Comments and at-rules (@media,
+// @font-face, ...) are dropped; the preview is a plain approximation of the exported page, not a second document.
+pub fn scope_css(css: &str, scope: &str) -> String {
+    let mut out = String::new();
+    for rule in strip_comments(css).split('}') {
+        let Some((selectors, body)) = rule.split_once('{') else { continue };
+        let selectors = selectors.trim();
+        if selectors.is_empty() || selectors.starts_with('@') {
+            continue;
+        }
+        let scoped = selectors.split(',')
+            .map(|s| scope_selector(s.trim(), scope))
+            .collect::<Vec<_>>()
+            .join(", ");
+        out.push_str(&format!("{} {{{}}}\n", scoped, body));
+    }
+    out
+}
+
+fn strip_comments(css: &str) -> String {
+    let mut out = String::with_capacity(css.len());
+    let mut rest = css;
+    while let Some(start) = rest.find("/*") {
+        out.push_str(&rest[..start]);
+        // an unclosed comment runs to the end, as in a browser
+        rest = rest[start + 2..].find("*/").map_or("", |end| &rest[start + 2 + end + 2..]);
+        out.push(' ');
+    }
+    out.push_str(rest);
+    out
+}
+
+// The selector with a leading `html`, `body` or `:root` (or `html body`) swapped for `scope`, or with
+// `scope` in front of it as an ancestor
+fn scope_selector(selector: &str, scope: &str) -> String {
+    let mut rest = selector;
+    let mut at_root = false;
+    while let Some(after) = strip_root(rest) {
+        at_root = true;
+        let next = after.trim_start();
+        if next.len() < after.len() && strip_root(next).is_some() {
+            rest = next;
+        } else {
+            rest = after;
+            break;
+        }
+    }
+    if at_root { format!("{}{}", scope, rest) } else { format!("{} {}", scope, rest) }
+}
+
+// What follows a leading root element in `selector`; a longer name such as `bodyx` doesn't count
+fn strip_root(selector: &str) -> Option<&str> {
+    ["html", "body", ":root"].iter().find_map(|root| {
+        let after = selector.strip_prefix(root)?;
+        let continues_name = after.chars().next().is_some_and(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        (!continues_name).then_some(after)
+    })
+}
+
+fn set_css_reset(reset: CssReset) {
+    if EDITOR_STATE.read().css_reset == reset {
+        return;
+    }
+    push_history();
+    EDITOR_STATE.write().css_reset = reset;
+}
+
+// Toggle and editor for the reset, in the page settings
+#[component]
+pub fn CssResetPanel() -> Element {
+    let reset = EDITOR_STATE.read().css_reset.clone();
+
+    rsx! {
+        div { style: "display: flex; flex-direction: column; gap: 6px; margin-top: 12px; font-size: 12px;",
+            label { style: "display: flex; align-items: center; gap: 6px;",
+                title: "Put a small reset in front of the page's CSS in the preview and the exported HTML/CSS",
+                input {
+                    r#type: "checkbox",
+                    checked: reset.enabled,
+                    onchange: {
+                        let reset = reset.clone();
+                        move |e: Event<FormData>| set_css_reset(CssReset { enabled: e.checked(), ..reset.clone() })
+                    },
+                }
+                "CSS reset"
+            }
+            if reset.enabled {
+                textarea {
+                    rows: "5",
+                    style: "font-family: monospace; font-size: 12px;",
+                    aria_label: "CSS reset",
+                    value: "{reset.css}",
+                    // one undo step per edit session, not per keystroke
+                    onchange: {
+                        let reset = reset.clone();
+                        move |e: Event<FormData>| set_css_reset(CssReset { css: e.value(), ..reset.clone() })
+                    },
+                }
+                button {
+                    disabled: reset.css == DEFAULT_CSS_RESET,
+                    onclick: move |_| set_css_reset(CssReset { enabled: true, css: DEFAULT_CSS_RESET.to_string() }),
+                    "Restore default reset"
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn selectors_are_prefixed_with_the_scope() {
+        assert_eq!(scope_css("p, h1 { margin: 0; }", ".root"), ".root p, .root h1 { margin: 0; }\n");
+        assert_eq!(scope_css("*::before{box-sizing:border-box}", ".root"), ".root *::before {box-sizing:border-box}\n");
+    }
+
+    #[test]
+    fn leading_html_and_body_become_the_scope() {
+        assert_eq!(scope_selector("body", ".root"), ".root");
+        assert_eq!(scope_selector("html body", ".root"), ".root");
+        assert_eq!(scope_selector(":root", ".root"), ".root");
+        assert_eq!(scope_selector("body > p", ".root"), ".root > p");
+        assert_eq!(scope_selector("body.dark p", ".root"), ".root.dark p");
+        assert_eq!(scope_selector("html > body a", ".root"), ".root > body a");
+        assert_eq!(scope_selector("bodyx", ".root"), ".root bodyx");
+        assert_eq!(scope_selector("p body", ".root"), ".root p body");
+    }
+
+    #[test]
+    fn comments_and_at_rules_are_dropped() {
+        let css = "/* reset */ body { margin: 0; }\n@media print { p { color: black; } }\nh1 /* big */ { margin: 0 }";
+        let scoped = scope_css(css, ".root");
+        assert!(scoped.starts_with(".root { margin: 0; }\n"));
+        assert!(scoped.contains(".root h1 { margin: 0 }"));
+        assert!(!scoped.contains("/*"));
+        assert!(!scoped.contains("@media"));
+    }
+
+    #[test]
+    fn unclosed_comment_runs_to_the_end() {
+        assert_eq!(scope_css("p { margin: 0; } /* h1 { margin: 0 }", ".root"), ".root p { margin: 0; }\n");
+    }
+}
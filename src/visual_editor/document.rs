@@ -0,0 +1,359 @@
+use std::collections::HashMap;
+use super::component::{Component, EditorState};
+use super::connections::{can_connect, can_move, ConnectError};
+
+// The component tree stored in EditorState's flat map (components listing their children by id).
+// Edits made through DocumentMut keep it a forest: every connection goes through can_connect, so a
+// component has at most one parent and no component ends up below itself. None of this records
+// history; the editor's undoable actions call push_history before using it.
+pub struct Document<'a> {
+    components: &'a HashMap<usize, Component>,
+}
+
+pub struct DocumentMut<'a> {
+    state: &'a mut EditorState,
+}
+
+impl EditorState {
+    pub fn document(&self) -> Document<'_> {
+        Document { components: &self.components }
+    }
+
+    pub fn document_mut(&mut self) -> DocumentMut<'_> {
+        DocumentMut { state: self }
+    }
+}
+
+impl<'a> Document<'a> {
+    // Components that aren't anyone's child, in insertion order (ids are handed out incrementally)
+    // so top-level sections render in the same sequence every time
+    pub fn roots(&self) -> Vec<usize> {
+        let mut roots = self.components.keys()
+            .copied()
+            .filter(|id| !self.components.values().any(|comp| comp.children.contains(id)))
+            .collect::<Vec<_>>();
+        roots.sort();
+        roots
+    }
+
+    // Children of `id` in order; empty for unknown ids
+    pub fn children_of(&self, id: usize) -> &'a [usize] {
+        self.components.get(&id).map(|c| c.children.as_slice()).unwrap_or(&[])
+    }
+
+    // Container listing `id` among its children (the lowest id if the state somehow has several)
+    pub fn parent_of(&self, id: usize) -> Option<usize> {
+        self.components.values()
+            .filter(|c| c.children.contains(&id))
+            .map(|c| c.id)
+            .min()
+    }
+
+    // Containers from the root down to the direct parent of `id`
+    pub fn ancestors(&self, id: usize) -> Vec<usize> {
+        let mut chain = Vec::new();
+        let mut current = id;
+        while let Some(parent) = self.parent_of(current) {
+            // guard against cycles in a corrupted document
+            if parent == id || chain.contains(&parent) {
+                break;
+            }
+            chain.push(parent);
+            current = parent;
+        }
+        chain.reverse();
+        chain
+    }
+
+    // Whether `target` is somewhere below `root` in the children graph
+    pub fn is_descendant(&self, root: usize, target: usize) -> bool {
+        let mut stack = vec![root];
+        let mut seen = Vec::new();
+        while let Some(id) = stack.pop() {
+            if seen.contains(&id) {
+                continue;
+            }
+            seen.push(id);
+            for &child in self.children_of(id) {
+                if child == target {
+                    return true;
+                }
+                stack.push(child);
+            }
+        }
+        false
+    }
+
+    // Every component in document order: roots in preview order, each followed by its subtree (as in the
+    // layers panel), then anything only reachable through a cycle by id
+    pub fn tree_order(&self) -> Vec<usize> {
+        let mut order = Vec::new();
+        let mut stack = self.roots();
+        stack.reverse();
+        let mut rest = self.components.keys().copied().collect::<Vec<_>>();
+        rest.sort();
+        rest.reverse();
+        while let Some(id) = stack.pop().or_else(|| rest.pop()) {
+            if order.contains(&id) {
+                continue;
+            }
+            order.push(id);
+            stack.extend(self.children_of(id).iter().rev().filter(|c| self.components.contains_key(c)));
+        }
+        order
+    }
+}
+
+impl DocumentMut<'_> {
+    // Insert `component` as a new root under the next free id, which is returned
+    pub fn add(&mut self, mut component: Component) -> usize {
+        let id = self.state.next_id;
+        self.state.next_id += 1;
+        component.id = id;
+        component.children.clear();
+        self.state.components.insert(id, component);
+        id
+    }
+
+    // Remove a component and unlink it from its parents; its own children become roots
+    pub fn remove(&mut self, id: usize) {
+        let state = &mut *self.state;
+        for component in state.components.values_mut() {
+            if component.children.contains(&id) {
+                component.children.retain(|&child_id| child_id != id);
+                component.touch();
+            }
+        }
+
+        state.components.remove(&id);
+        // instances of a deleted master keep their current look
+        for component in state.components.values_mut().filter(|c| c.master_id == Some(id)) {
+            component.master_id = None;
+        }
+        state.edge_styles.retain(|&(parent, child), _| parent != id && child != id);
+        if state.selected_edge.is_some_and(|(parent, child)| parent == id || child == id) {
+            state.selected_edge = None;
+        }
+
+        state.selected_ids.retain(|&s| s != id);
+        if state.selected_id == Some(id) {
+            state.selected_id = state.selected_ids.last().copied();
+        }
+    }
+
+    // Make `child` the last child of `parent`; connecting an existing pair is a no-op
+    pub fn connect(&mut self, parent: usize, child: usize) -> Result<(), ConnectError> {
+        can_connect(self.state, parent, child)?;
+        let comp = self.state.components.get_mut(&parent).ok_or(ConnectError::NotFound(parent))?;
+        if !comp.children.contains(&child) {
+            comp.children.push(child);
+            comp.touch();
+        }
+        Ok(())
+    }
+
+    // Remove `child` from `parent`'s children, dropping the arrow's style
+    pub fn disconnect(&mut self, parent: usize, child: usize) -> Result<(), ConnectError> {
+        let comp = self.state.components.get_mut(&parent).ok_or(ConnectError::NotFound(parent))?;
+        if !comp.children.contains(&child) {
+            return Err(ConnectError::NotFound(child));
+        }
+        comp.children.retain(|&id| id != child);
+        comp.touch();
+        self.state.edge_styles.remove(&(parent, child));
+        if self.state.selected_edge == Some((parent, child)) {
+            self.state.selected_edge = None;
+        }
+        Ok(())
+    }
+
+    // Put `new_child` in `old_child`'s slot among `parent`'s children, carrying the arrow's style over.
+    // `new_child` is taken out of any other slot it had under `parent`.
+    pub fn replace_child(&mut self, parent: usize, old_child: usize, new_child: usize) -> Result<(), ConnectError> {
+        if old_child == new_child {
+            return Ok(());
+        }
+        can_connect(self.state, parent, new_child)?;
+        let comp = self.state.components.get_mut(&parent).ok_or(ConnectError::NotFound(parent))?;
+        if !comp.children.contains(&old_child) {
+            return Err(ConnectError::NotFound(old_child));
+        }
+        comp.children.retain(|&id| id != new_child);
+        if let Some(slot) = comp.children.iter_mut().find(|id| **id == old_child) {
+            *slot = new_child;
+        }
+        comp.touch();
+        let state = &mut *self.state;
+        if let Some(style) = state.edge_styles.remove(&(parent, old_child)) {
+            state.edge_styles.insert((parent, new_child), style);
+        }
+        if state.selected_edge == Some((parent, old_child)) {
+            state.selected_edge = Some((parent, new_child));
+        }
+        Ok(())
+    }
+
+    // Move `child` into `parent` at `index` of its children, or to the top level when `parent` is None.
+    // The child leaves every container it was in; reordering within the same parent keeps the arrow's style.
+    pub fn move_to(&mut self, child: usize, parent: Option<usize>, index: usize) -> Result<(), ConnectError> {
+        if !self.state.components.contains_key(&child) {
+            return Err(ConnectError::NotFound(child));
+        }
+        if let Some(parent) = parent {
+            can_move(self.state, parent, child)?;
+        }
+
+        let state = &mut *self.state;
+        let mut index = index;
+        let mut old_parents = Vec::new();
+        for comp in state.components.values_mut() {
+            if let Some(pos) = comp.children.iter().position(|&id| id == child) {
+                // `index` counts the child's old slot when it stays under the same parent
+                if Some(comp.id) == parent && pos < index {
+                    index -= 1;
+                }
+                comp.children.retain(|&id| id != child);
+                comp.touch();
+                old_parents.push(comp.id);
+            }
+        }
+        if let Some(comp) = parent.and_then(|parent| state.components.get_mut(&parent)) {
+            let index = index.min(comp.children.len());
+            comp.children.insert(index, child);
+            comp.touch();
+        }
+        for old in old_parents.into_iter().filter(|&old| Some(old) != parent) {
+            state.edge_styles.remove(&(old, child));
+            if state.selected_edge == Some((old, child)) {
+                state.selected_edge = None;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::visual_editor::component::ComponentType;
+    use crate::visual_editor::connections::EdgeStyle;
+
+    fn add(state: &mut EditorState, component_type: ComponentType) -> usize {
+        state.document_mut().add(Component::new(0, component_type, 0.0, 0.0))
+    }
+
+    // #0 Container > (#1 Container > #3 Heading, #2 Paragraph); #4 Container on its own
+    fn sample() -> EditorState {
+        let mut state = EditorState::default();
+        for component_type in [ComponentType::Container, ComponentType::Container, ComponentType::Paragraph,
+            ComponentType::Heading, ComponentType::Container] {
+            add(&mut state, component_type);
+        }
+        let mut doc = state.document_mut();
+        doc.connect(0, 1).unwrap();
+        doc.connect(0, 2).unwrap();
+        doc.connect(1, 3).unwrap();
+        state
+    }
+
+    #[test]
+    fn add_hands_out_ids_and_drops_children() {
+        let mut state = EditorState::default();
+        let mut component = Component::new(42, ComponentType::Container, 0.0, 0.0);
+        component.children = vec![7];
+        assert_eq!(state.document_mut().add(component), 0);
+        assert_eq!(add(&mut state, ComponentType::Heading), 1);
+        assert!(state.components[&0].children.is_empty());
+        assert_eq!(state.next_id, 2);
+    }
+
+    #[test]
+    fn roots_parents_and_ancestors() {
+        let state = sample();
+        let doc = state.document();
+        assert_eq!(doc.roots(), vec![0, 4]);
+        assert_eq!(doc.parent_of(3), Some(1));
+        assert_eq!(doc.parent_of(0), None);
+        assert_eq!(doc.ancestors(3), vec![0, 1]);
+        assert!(doc.children_of(99).is_empty());
+    }
+
+    #[test]
+    fn is_descendant_follows_the_whole_subtree() {
+        let state = sample();
+        let doc = state.document();
+        assert!(doc.is_descendant(0, 3));
+        assert!(doc.is_descendant(1, 3));
+        assert!(!doc.is_descendant(3, 0));
+        assert!(!doc.is_descendant(2, 3));
+        assert!(!doc.is_descendant(4, 3));
+    }
+
+    #[test]
+    fn tree_order_is_depth_first_from_the_roots() {
+        let state = sample();
+        assert_eq!(state.document().tree_order(), vec![0, 1, 3, 2, 4]);
+    }
+
+    #[test]
+    fn connect_rejects_cycles_self_links_and_second_parents() {
+        let mut state = sample();
+        let mut doc = state.document_mut();
+        assert_eq!(doc.connect(1, 0), Err(ConnectError::Cycle));
+        assert_eq!(doc.connect(4, 4), Err(ConnectError::SelfLink));
+        assert_eq!(doc.connect(4, 3), Err(ConnectError::AlreadyHasParent(1)));
+        assert_eq!(doc.connect(3, 4), Err(ConnectError::NotContainer(3)));
+        // connecting an existing pair again is a no-op
+        assert_eq!(doc.connect(0, 1), Ok(()));
+        assert_eq!(state.components[&0].children, vec![1, 2]);
+    }
+
+    #[test]
+    fn move_to_counts_the_old_slot_within_the_same_parent() {
+        let mut state = sample();
+        let extra = add(&mut state, ComponentType::Heading);
+        state.document_mut().connect(0, extra).unwrap();
+        // [1, 2, extra]: index 2 is the slot before `extra` counted with #1 still in place
+        state.document_mut().move_to(1, Some(0), 2).unwrap();
+        assert_eq!(state.components[&0].children, vec![2, 1, extra]);
+        state.document_mut().move_to(extra, Some(0), 0).unwrap();
+        assert_eq!(state.components[&0].children, vec![extra, 2, 1]);
+    }
+
+    #[test]
+    fn move_to_another_parent_or_the_top_level() {
+        let mut state = sample();
+        state.edge_styles.insert((0, 2), EdgeStyle::default());
+        state.document_mut().move_to(2, Some(4), 0).unwrap();
+        assert_eq!(state.components[&0].children, vec![1]);
+        assert_eq!(state.components[&4].children, vec![2]);
+        assert!(!state.edge_styles.contains_key(&(0, 2)));
+
+        state.document_mut().move_to(2, None, 0).unwrap();
+        assert!(state.components[&4].children.is_empty());
+        assert_eq!(state.document().roots(), vec![0, 2, 4]);
+        assert_eq!(state.document_mut().move_to(0, Some(3), 0), Err(ConnectError::NotContainer(3)));
+    }
+
+    #[test]
+    fn replace_child_keeps_the_slot_and_the_arrow_style() {
+        let mut state = sample();
+        let style = EdgeStyle { color: "#ff0000".to_string(), dashed: true };
+        state.edge_styles.insert((0, 1), style.clone());
+        state.document_mut().replace_child(0, 1, 4).unwrap();
+        assert_eq!(state.components[&0].children, vec![4, 2]);
+        assert_eq!(state.edge_styles.get(&(0, 4)), Some(&style));
+        let loose = add(&mut state, ComponentType::Heading);
+        assert_eq!(state.document_mut().replace_child(0, 1, loose), Err(ConnectError::NotFound(1)));
+    }
+
+    #[test]
+    fn remove_unlinks_and_promotes_children() {
+        let mut state = sample();
+        state.edge_styles.insert((1, 3), EdgeStyle::default());
+        state.document_mut().remove(1);
+        assert_eq!(state.components[&0].children, vec![2]);
+        assert_eq!(state.document().roots(), vec![0, 3, 4]);
+        assert!(state.edge_styles.is_empty());
+    }
+}
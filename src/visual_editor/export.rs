@@ -0,0 +1,324 @@
+use dioxus::prelude::*;
+use std::collections::HashMap;
+use super::component::{position_offset, position_styles, ComponentType, EditorState, EDITOR_STATE};
+use super::registry::renderer_for;
+use super::project::{export_state_json, export_subtree, ImportPanel};
+use super::graph::{export_diagram_svg, export_graph_dot, export_graph_json};
+use super::rich_text::inline_html;
+use super::anchors::{duplicate_anchor_ids, exported_anchor, link_href};
+use super::bundle::export_bundle;
+
+// Class name a component gets in exported CSS/HTML
+pub fn component_class(id: usize) -> String {
+    format!("comp-{}", id)
+}
+
+// Export the page styles as a `body` rule plus one rule per component class.
+// Pretty-printed by default; `minify` strips whitespace and newlines (`.comp-1{color:red}`).
+pub fn export_css(minify: bool) -> String {
+    let state = EDITOR_STATE.read();
+    css_for_state(&state, minify)
+}
+
+fn css_for_state(state: &EditorState, minify: bool) -> String {
+    // HashMap iteration is unordered, sort so output is stable between exports
+    let mut ids = state.components.keys().copied().collect::<Vec<_>>();
+    ids.sort();
+
+    let mut out = String::new();
+    // the reset goes first so the page's own rules win
+    if state.css_reset.enabled && !state.css_reset.css.trim().is_empty() {
+        if minify {
+            out.push_str(&state.css_reset.css.split_whitespace().collect::<Vec<_>>().join(" "));
+        } else {
+            out.push_str(&format!("{}\n\n", state.css_reset.css.trim()));
+        }
+    }
+    if !state.page_styles.is_empty() {
+        push_rule(&mut out, "body", &state.page_styles, minify);
+    }
+    for id in ids {
+        let mut styles = state.components[&id].styles.clone();
+        if let Some(offset) = position_offset(state, id) {
+            styles.extend(position_styles(offset).map(|(k, v)| (k.to_string(), v)));
+        }
+        if !styles.is_empty() {
+            push_rule(&mut out, &format!(".{}", component_class(id)), &styles, minify);
+        }
+    }
+
+    if minify { out } else { out.trim_end().to_string() }
+}
+
+fn push_rule(out: &mut String, selector: &str, styles: &HashMap<String, String>, minify: bool) {
+    let mut styles = styles.iter().collect::<Vec<_>>();
+    styles.sort_by(|a, b| a.0.cmp(b.0));
+
+    if minify {
+        let body = styles.iter()
+            .map(|(k, v)| format!("{}:{}", k.trim(), v.trim()))
+            .collect::<Vec<_>>()
+            .join(";");
+        out.push_str(&format!("{}{{{}}}", selector, body));
+    } else {
+        out.push_str(&format!("{} {{\n", selector));
+        for (k, v) in styles {
+            out.push_str(&format!("  {}: {};\n", k, v));
+        }
+        out.push_str("}\n\n");
+    }
+}
+
+// Export a standalone HTML page: exported CSS in <style>, root components in preview order inside <body>
+pub fn export_html() -> String {
+    html_for_state(&EDITOR_STATE.read())
+}
+
+pub fn html_for_state(state: &EditorState) -> String {
+    let mut body = String::new();
+    for id in state.document().roots() {
+        push_component_html(state, id, 1, &mut body);
+    }
+
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<style>\n{}\n</style>\n</head>\n<body>\n{}</body>\n</html>\n",
+        css_for_state(state, false),
+        body
+    )
+}
+
+fn push_component_html(state: &EditorState, id: usize, depth: usize, out: &mut String) {
+    let Some(component) = state.components.get(&id) else { return };
+    if !component.visible {
+        return;
+    }
+    let indent = "  ".repeat(depth);
+    let class = component_class(component.id);
+    // a duplicated anchor only goes on its first component, so the page's ids stay unique
+    let attrs = match exported_anchor(state, component.id) {
+        Some(anchor) => format!("class=\"{}\" id=\"{}\"", class, escape_html(&anchor)),
+        None => format!("class=\"{}\"", class),
+    };
+    let content = escape_html(&component.content);
+
+    match &component.component_type {
+        ComponentType::Container => {
+            out.push_str(&format!("{}<div {}>\n", indent, attrs));
+            for child_id in component.children.iter() {
+                push_component_html(state, *child_id, depth + 1, out);
+            }
+            out.push_str(&format!("{}</div>\n", indent));
+        }
+        // text plus the bold/italic/underline tags from the formatting toolbar
+        ComponentType::Heading => out.push_str(&format!("{}<h1 {}>{}</h1>\n", indent, attrs, inline_html(&component.content))),
+        ComponentType::Paragraph => out.push_str(&format!("{}<p {}>{}</p>\n", indent, attrs, inline_html(&component.content))),
+        ComponentType::Form => {
+            out.push_str(&format!("{}<form {}>\n", indent, attrs));
+            for child_id in component.children.iter() {
+                push_component_html(state, *child_id, depth + 1, out);
+            }
+            out.push_str(&format!("{}</form>\n", indent));
+        }
+        ComponentType::Input => {
+            let input_type = escape_html(component.input_type.as_deref().unwrap_or("text"));
+            out.push_str(&format!("{}<input {} type=\"{}\" placeholder=\"{}\">\n", indent, attrs, input_type, content));
+        }
+        ComponentType::Divider => out.push_str(&format!("{}<hr {}>\n", indent, attrs)),
+        // the height comes from the component's class in the stylesheet
+        ComponentType::Spacer => out.push_str(&format!("{}<div {}></div>\n", indent, attrs)),
+        // written verbatim, unescaped: the user pasted it as HTML
+        ComponentType::RawHtml => out.push_str(&format!("{}<div {}>{}</div>\n", indent, attrs, component.content)),
+        ComponentType::Image => {
+            let alt = escape_html(component.name.as_deref().unwrap_or(""));
+            out.push_str(&format!("{}<img {} src=\"{}\" alt=\"{}\">\n", indent, attrs, content, alt));
+        }
+        ComponentType::Link => {
+            let href = escape_html(&link_href(state, component.id));
+            out.push_str(&format!("{}<a {} href=\"{}\">{}</a>\n", indent, attrs, href, content));
+        }
+        ComponentType::Quote => {
+            let cite = component.cite.as_ref().map(|c| format!(" cite=\"{}\"", escape_html(c))).unwrap_or_default();
+            out.push_str(&format!("{}<blockquote {}{}>{}</blockquote>\n", indent, attrs, cite, content));
+        }
+        ComponentType::Custom(name) => {
+            let inner = renderer_for(name).export(component);
+            out.push_str(&format!("{}<div {}>{}</div>\n", indent, attrs, inner));
+        }
+    }
+}
+
+// Visible top-level components that aren't containers, in page order. When the page also has containers
+// these usually lost their parent (e.g. it was deleted) and would float loose in the exported page.
+pub fn loose_root_components(state: &EditorState) -> Vec<usize> {
+    let roots = state.document().roots().into_iter()
+        .filter(|id| state.components[id].visible)
+        .collect::<Vec<_>>();
+    let has_container = roots.iter().any(|id| state.components[id].component_type.is_container());
+    if !has_container {
+        return Vec::new();
+    }
+    roots.into_iter()
+        .filter(|id| !state.components[id].component_type.is_container())
+        .collect()
+}
+
+fn finish_html_export(mut output: Signal<String>) {
+    output.set(export_html());
+    // exporting the page counts as saving it
+    EDITOR_STATE.write().dirty = false;
+}
+
+pub fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[component]
+pub fn ExportPanel() -> Element {
+    let mut minify = use_signal(|| false);
+    let mut output = use_signal(String::new);
+    // loose top-level components found when Export HTML was clicked, waiting for confirmation
+    let mut loose_roots = use_signal(Vec::<String>::new);
+    let duplicate_anchors = duplicate_anchor_ids(&EDITOR_STATE.read()).into_iter()
+        .map(|a| format!("#{}", a))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    rsx! {
+        div { style: "display: flex; flex-direction: column; gap: 8px;",
+            label { style: "font-size: 12px; display: flex; align-items: center; gap: 6px;",
+                input {
+                    r#type: "checkbox",
+                    checked: minify(),
+                    onchange: move |e| minify.set(e.checked()),
+                }
+                "Minify"
+            }
+            div { style: "display: flex; gap: 8px;",
+                button {
+                    onclick: move |_| output.set(export_css(minify())),
+                    "Export CSS"
+                }
+                button {
+                    onclick: move |_| {
+                        let loose = {
+                            let state = EDITOR_STATE.read();
+                            loose_root_components(&state).into_iter()
+                                .map(|id| format!("{} #{}", state.components[&id].component_type.label(), id))
+                                .collect::<Vec<_>>()
+                        };
+                        if loose.is_empty() {
+                            finish_html_export(output);
+                        } else {
+                            loose_roots.set(loose);
+                        }
+                    },
+                    "Export HTML"
+                }
+                // exporting the project counts as saving it
+                button {
+                    onclick: move |_| { output.set(export_state_json()); EDITOR_STATE.write().dirty = false; },
+                    "Export JSON"
+                }
+            }
+            button {
+                disabled: EDITOR_STATE.read().selected_id.is_none(),
+                title: "Just the selected component and its children, for importing into another project",
+                onclick: move |_| {
+                    let selected = EDITOR_STATE.read().selected_id;
+                    if let Some(json) = selected.and_then(export_subtree) {
+                        output.set(json);
+                    }
+                },
+                "Export selected subtree (JSON)"
+            }
+            button {
+                title: "index.html plus an assets/ folder holding the images that are embedded as data URLs",
+                onclick: move |_| output.set(export_bundle().unwrap_or_else(|e| e)),
+                "Export bundle (.zip)"
+            }
+            if !duplicate_anchors.is_empty() {
+                p { style: "margin: 0; font-size: 12px; padding: 8px; background: #fff3e0; border: 1px solid #FF9800; border-radius: 4px;",
+                    "Anchors used by more than one component: {duplicate_anchors}. Only the lowest-numbered component keeps each in the exported HTML."
+                }
+            }
+            if !loose_roots().is_empty() {
+                div { style: "font-size: 12px; padding: 8px; background: #fff3e0; border: 1px solid #FF9800; border-radius: 4px;",
+                    p { style: "margin: 0 0 6px 0;",
+                        "These components aren't inside any container and will appear at the top level of the page:"
+                    }
+                    ul { style: "margin: 0 0 6px 0; padding-left: 18px;",
+                        for name in loose_roots() {
+                            li { "{name}" }
+                        }
+                    }
+                    div { style: "display: flex; gap: 8px;",
+                        button {
+                            onclick: move |_| { loose_roots.set(Vec::new()); finish_html_export(output); },
+                            "Export anyway"
+                        }
+                        button {
+                            onclick: move |_| loose_roots.set(Vec::new()),
+                            "Cancel"
+                        }
+                    }
+                }
+            }
+            // structure only: the parent -> child arrows without styles or content (the SVG also draws the canvas layout)
+            div { style: "display: flex; gap: 8px;",
+                button {
+                    onclick: move |_| output.set(export_graph_json()),
+                    "Export graph (JSON)"
+                }
+                button {
+                    onclick: move |_| output.set(export_graph_dot()),
+                    "Export graph (DOT)"
+                }
+                button {
+                    title: "The editor canvas, boxes and arrows, as an image",
+                    onclick: move |_| output.set(export_diagram_svg()),
+                    "Export diagram (SVG)"
+                }
+            }
+            if !output().is_empty() {
+                textarea {
+                    readonly: true,
+                    rows: "8",
+                    style: "width: 100%; box-sizing: border-box; font-family: monospace; font-size: 11px;",
+                    value: "{output}",
+                }
+            }
+            label { style: "font-size: 12px; display: flex; align-items: center; gap: 6px;",
+                input {
+                    r#type: "checkbox",
+                    checked: EDITOR_STATE.read().warn_on_unload,
+                    onchange: move |e| EDITOR_STATE.write().warn_on_unload = e.checked(),
+                }
+                "Warn before leaving with unsaved changes"
+            }
+            ImportPanel {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::visual_editor::component::Component;
+
+    #[test]
+    fn relative_components_are_offset_from_their_parent() {
+        let mut state = EditorState::default();
+        let mut doc = state.document_mut();
+        let container = doc.add(Component::new(0, ComponentType::Container, 100.0, 50.0));
+        let heading = doc.add(Component::new(0, ComponentType::Heading, 130.0, 250.0));
+        doc.connect(container, heading).unwrap();
+        assert!(!css_for_state(&state, true).contains("position"));
+
+        state.components.get_mut(&heading).unwrap().relative_position = true;
+        assert_eq!(position_offset(&state, heading), Some((30.0, 200.0)));
+        assert_eq!(css_for_state(&state, true), ".comp-1{left:30px;position:relative;top:200px}");
+    }
+}
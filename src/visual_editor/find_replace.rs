@@ -0,0 +1,172 @@
+use dioxus::prelude::*;
+use super::component::{ComponentType, EDITOR_STATE};
+use super::history::push_history;
+use super::rich_text::tag_at;
+use super::symbols::sync_instances;
+
+// Types whose content is text shown on the page. Image sources, raw HTML and custom content aren't searched.
+fn is_searchable(component_type: &ComponentType) -> bool {
+    matches!(component_type, ComponentType::Heading | ComponentType::Paragraph | ComponentType::Quote | ComponentType::Link)
+}
+
+// Replace `find` in the content of every text component as a single undoable step; returns the number of replacements
+pub fn find_replace(find: &str, replace: &str, case_sensitive: bool) -> usize {
+    if find.is_empty() {
+        return 0;
+    }
+
+    // compute the new contents under a read lock first so nothing is recorded when there are no matches
+    let updates = {
+        let state = EDITOR_STATE.read();
+        state.components.iter().filter(|(_, comp)| is_searchable(&comp.component_type)).filter_map(|(&id, comp)| {
+            let (content, count) = replace_matches(&comp.content, find, replace, case_sensitive);
+            (count > 0).then_some((id, content, count))
+        }).collect::<Vec<_>>()
+    };
+
+    let total = updates.iter().map(|(_, _, count)| count).sum();
+    if total == 0 {
+        return 0;
+    }
+
+    push_history();
+    let mut state = EDITOR_STATE.write();
+    for (id, content, _) in updates.iter() {
+        if let Some(component) = state.components.get_mut(id) {
+            component.content = content.clone();
+            component.touch();
+        }
+    }
+    // masters win over instances that were also edited
+    for (id, _, _) in updates {
+        sync_instances(&mut state, id);
+    }
+    total
+}
+
+// Replace `find` in the text between formatting tags; the tags themselves never match, so
+// replacing "strong" leaves `<strong>` intact. Matches don't span a tag.
+fn replace_matches(text: &str, find: &str, replace: &str, case_sensitive: bool) -> (String, usize) {
+    if find.is_empty() {
+        return (text.to_string(), 0);
+    }
+
+    let mut out = String::with_capacity(text.len());
+    let mut count = 0;
+    let mut rest = text;
+    while !rest.is_empty() {
+        let tag = rest.char_indices().find_map(|(i, _)| tag_at(&rest[i..]).map(|(_, _, len)| (i, len)));
+        let (text_end, tag_len) = tag.unwrap_or((rest.len(), 0));
+        let (replaced, n) = replace_in_text(&rest[..text_end], find, replace, case_sensitive);
+        out.push_str(&replaced);
+        out.push_str(&rest[text_end..text_end + tag_len]);
+        count += n;
+        rest = &rest[text_end + tag_len..];
+    }
+    (out, count)
+}
+
+fn replace_in_text(text: &str, find: &str, replace: &str, case_sensitive: bool) -> (String, usize) {
+    if case_sensitive {
+        return (text.replace(find, replace), text.matches(find).count());
+    }
+
+    let mut out = String::with_capacity(text.len());
+    let mut count = 0;
+    let mut rest = text;
+    while let Some(ch) = rest.chars().next() {
+        if let Some(len) = prefix_len_ignore_case(rest, find) {
+            out.push_str(replace);
+            rest = &rest[len..];
+            count += 1;
+        } else {
+            out.push(ch);
+            rest = &rest[ch.len_utf8()..];
+        }
+    }
+    (out, count)
+}
+
+// Byte length of the prefix of `text` that matches `pattern` ignoring case, if any
+fn prefix_len_ignore_case(text: &str, pattern: &str) -> Option<usize> {
+    let mut chars = text.char_indices();
+    for p in pattern.chars() {
+        let (_, t) = chars.next()?;
+        if !t.to_lowercase().eq(p.to_lowercase()) {
+            return None;
+        }
+    }
+    Some(chars.next().map(|(i, _)| i).unwrap_or(text.len()))
+}
+
+#[component]
+pub fn FindReplacePanel() -> Element {
+    let mut find = use_signal(String::new);
+    let mut replace = use_signal(String::new);
+    let mut case_sensitive = use_signal(|| false);
+    let mut result = use_signal(|| None::<usize>);
+
+    rsx! {
+        div { style: "display: flex; flex-direction: column; gap: 8px;",
+            input {
+                placeholder: "Find",
+                value: "{find}",
+                oninput: move |e| { find.set(e.value()); result.set(None); },
+            }
+            input {
+                placeholder: "Replace with",
+                value: "{replace}",
+                oninput: move |e| replace.set(e.value()),
+            }
+            label { style: "font-size: 12px; display: flex; align-items: center; gap: 6px;",
+                input {
+                    r#type: "checkbox",
+                    checked: case_sensitive(),
+                    onchange: move |e| case_sensitive.set(e.checked()),
+                }
+                "Case sensitive"
+            }
+            button {
+                disabled: find().is_empty(),
+                onclick: move |_| result.set(Some(find_replace(&find(), &replace(), case_sensitive()))),
+                "Replace all"
+            }
+            if let Some(count) = result() {
+                p { style: "font-size: 12px; margin: 0;", "Replaced {count} occurrence(s)" }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_ignore_case_unless_asked() {
+        assert_eq!(replace_matches("Cat, cat, CAT", "cat", "dog", false), ("dog, dog, dog".to_string(), 3));
+        assert_eq!(replace_matches("Cat, cat, CAT", "cat", "dog", true), ("Cat, dog, CAT".to_string(), 1));
+    }
+
+    #[test]
+    fn empty_find_changes_nothing() {
+        assert_eq!(replace_matches("text", "", "x", false), ("text".to_string(), 0));
+        assert_eq!(replace_matches("text", "", "x", true), ("text".to_string(), 0));
+    }
+
+    #[test]
+    fn formatting_tags_are_left_alone() {
+        let (out, count) = replace_matches("a <strong>strong</strong> <em>word</em>", "strong", "bold", false);
+        assert_eq!(out, "a <strong>bold</strong> <em>word</em>");
+        assert_eq!(count, 1);
+        assert_eq!(replace_matches("<u>u</u>", "u", "x", true), ("<u>x</u>".to_string(), 1));
+    }
+
+    #[test]
+    fn only_text_types_are_searched() {
+        assert!(is_searchable(&ComponentType::Paragraph));
+        assert!(is_searchable(&ComponentType::Link));
+        assert!(!is_searchable(&ComponentType::Image));
+        assert!(!is_searchable(&ComponentType::RawHtml));
+    }
+}
@@ -0,0 +1,157 @@
+use dioxus::prelude::*;
+use super::component::{round_coord, Component, ComponentType, BOX_WIDTH, DEFAULT_BOX_HEIGHT, EDITOR_STATE};
+use super::history::push_history;
+use super::layout::{LEVEL_GAP, SIBLING_GAP};
+use super::viewport::{canvas_size, screen_to_canvas};
+
+const GALLERY_INPUT_ID: &str = "gallery-file-input";
+// A new gallery lays its images out in a wrapping row
+const GALLERY_STYLES: [(&str, &str); 3] = [("display", "flex"), ("flex-wrap", "wrap"), ("gap", "8px")];
+const GALLERY_IMAGE_WIDTH: &str = "200px";
+
+// Add a container holding one Image per (file name, source), as one undoable step, and select it.
+// On the canvas the container sits in the middle of the view with its images in a row underneath.
+// Returns the container's id; None when there are no images.
+pub fn add_gallery(images: Vec<(String, String)>) -> Option<usize> {
+    if images.is_empty() {
+        return None;
+    }
+    let (width, height) = canvas_size();
+    let (cx, cy) = screen_to_canvas(width / 2.0, height / 2.0);
+    let count = images.len() as f64;
+    let row_width = count * BOX_WIDTH + (count - 1.0) * SIBLING_GAP;
+    let top = cy - (2.0 * DEFAULT_BOX_HEIGHT + LEVEL_GAP) / 2.0;
+    let row_y = top + DEFAULT_BOX_HEIGHT + LEVEL_GAP;
+
+    push_history();
+    let mut state = EDITOR_STATE.write();
+    let precision = state.coord_precision;
+    // document_mut().add assigns the ids
+    let mut gallery = Component::new(0, ComponentType::Container, round_coord(cx - BOX_WIDTH / 2.0, precision), round_coord(top, precision));
+    gallery.name = Some("Gallery".to_string());
+    gallery.styles = GALLERY_STYLES.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+    let gallery_id = state.document_mut().add(gallery);
+
+    for (i, (file_name, src)) in images.into_iter().enumerate() {
+        let x = cx - row_width / 2.0 + i as f64 * (BOX_WIDTH + SIBLING_GAP);
+        let mut image = Component::new(0, ComponentType::Image, round_coord(x, precision), round_coord(row_y, precision));
+        image.content = src;
+        image.name = Some(file_name);
+        image.styles.insert("width".to_string(), GALLERY_IMAGE_WIDTH.to_string());
+        let id = state.document_mut().add(image);
+        // only a child policy forbidding images in containers stops this; the image then stays a root
+        let _ = state.document_mut().connect(gallery_id, id);
+    }
+    state.select(Some(gallery_id));
+    Some(gallery_id)
+}
+
+// Read the image files picked in the gallery input as data URLs and add them as a gallery, in the order
+// they were picked. Files that aren't images or fail to read are skipped.
+fn import_picked_files(e: FormEvent) {
+    #[cfg(target_arch = "wasm32")]
+    {
+        let _ = e;
+        use std::cell::{Cell, RefCell};
+        use std::rc::Rc;
+        use wasm_bindgen::JsCast;
+
+        let Some(input) = web_sys::window()
+            .and_then(|w| w.document())
+            .and_then(|d| d.get_element_by_id(GALLERY_INPUT_ID))
+            .and_then(|e| e.dyn_into::<web_sys::HtmlInputElement>().ok()) else { return };
+        let Some(files) = input.files() else { return };
+        let files = (0..files.length())
+            .filter_map(|i| files.get(i))
+            .filter(|f| f.type_().starts_with("image/"))
+            .collect::<Vec<_>>();
+        // clear the input so picking the same files again still fires onchange
+        input.set_value("");
+        if files.is_empty() {
+            return;
+        }
+
+        let loaded = Rc::new(RefCell::new(vec![None; files.len()]));
+        let remaining = Rc::new(Cell::new(files.len()));
+        // reader callbacks run outside the Dioxus runtime; add_gallery needs it to write the state
+        let finish = Rc::new(Runtime::wrap_closure(move |images: Vec<(String, String)>| { add_gallery(images); }));
+        for (i, file) in files.into_iter().enumerate() {
+            let Ok(reader) = web_sys::FileReader::new() else {
+                remaining.set(remaining.get() - 1);
+                continue;
+            };
+            let on_loadend = {
+                let (loaded, remaining, finish, name) = (loaded.clone(), remaining.clone(), finish.clone(), file.name());
+                let reader = reader.clone();
+                // loadend fires after both success and failure
+                wasm_bindgen::prelude::Closure::once_into_js(move || {
+                    if let Some(src) = reader.result().ok().and_then(|r| r.as_string()) {
+                        loaded.borrow_mut()[i] = Some((name, src));
+                    }
+                    remaining.set(remaining.get() - 1);
+                    if remaining.get() == 0 {
+                        finish(loaded.borrow_mut().drain(..).flatten().collect());
+                    }
+                })
+            };
+            reader.set_onloadend(Some(on_loadend.unchecked_ref()));
+            if reader.read_as_data_url(&file).is_err() {
+                reader.set_onloadend(None);
+                remaining.set(remaining.get() - 1);
+            }
+        }
+        // every reader failed to start
+        if remaining.get() == 0 {
+            finish(Vec::new());
+        }
+    }
+
+    // no FileReader outside the browser: the webview loads the picked files from disk by path
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        let Some(engine) = e.files() else { return };
+        let images = engine.files().into_iter()
+            .map(|path| {
+                let name = std::path::Path::new(&path).file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+                (name, format!("file://{}", path))
+            })
+            .collect();
+        add_gallery(images);
+    }
+}
+
+// Open the (hidden) gallery file input's picker
+fn open_picker() {
+    #[cfg(target_arch = "wasm32")]
+    {
+        use wasm_bindgen::JsCast;
+        if let Some(input) = web_sys::window()
+            .and_then(|w| w.document())
+            .and_then(|d| d.get_element_by_id(GALLERY_INPUT_ID))
+            .and_then(|e| e.dyn_into::<web_sys::HtmlElement>().ok())
+        {
+            input.click();
+        }
+    }
+}
+
+// "Add image gallery" button; picks several image files at once
+#[component]
+pub fn GalleryImport() -> Element {
+    rsx! {
+        input {
+            id: GALLERY_INPUT_ID,
+            r#type: "file",
+            accept: "image/*",
+            multiple: true,
+            style: "display: none;",
+            onchange: import_picked_files,
+        }
+        button {
+            style: "margin-top: 8px;",
+            title: "Pick several images to add them as Image components inside a new container",
+            onclick: move |_| open_picker(),
+            "Add image gallery…"
+        }
+    }
+}
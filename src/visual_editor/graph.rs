@@ -0,0 +1,133 @@
+use dioxus::prelude::*;
+use serde::Serialize;
+use super::component::{rect_edge_point_towards, EditorState, EDITOR_STATE};
+use super::connections::edge_style;
+use super::export::escape_html;
+use super::rich_text::plain_text;
+use super::routing::{route_arrow, svg_points};
+
+// Space around the boxes in the diagram export
+const DIAGRAM_MARGIN: f64 = 20.0;
+// Longest content excerpt shown in a diagram box, in characters
+const DIAGRAM_EXCERPT_CHARS: usize = 24;
+
+// One parent -> child arrow
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Edge {
+    pub from: usize,
+    pub to: usize,
+}
+
+// Every connection in the document, ordered by parent id and then child order
+pub fn graph_edges(state: &EditorState) -> Vec<Edge> {
+    let mut ids = state.components.keys().copied().collect::<Vec<_>>();
+    ids.sort();
+
+    ids.into_iter()
+        .flat_map(|from| state.components[&from].children.iter().map(move |&to| Edge { from, to }))
+        .collect()
+}
+
+// The connection graph as a JSON array of `{"from": .., "to": ..}` objects
+pub fn export_graph_json() -> String {
+    let state = EDITOR_STATE.read();
+    serde_json::to_string_pretty(&graph_edges(&state)).unwrap_or_default()
+}
+
+// The connection graph in Graphviz DOT format; every component is a node so unconnected ones show up too
+pub fn export_graph_dot() -> String {
+    let state = EDITOR_STATE.read();
+    let mut ids = state.components.keys().copied().collect::<Vec<_>>();
+    ids.sort();
+
+    let mut out = String::from("digraph components {\n");
+    for id in ids {
+        let label = format!("{} #{}", state.components[&id].component_type.label(), id);
+        out.push_str(&format!("  n{} [label=\"{}\"];\n", id, label.replace('\\', "\\\\").replace('"', "\\\"")));
+    }
+    for edge in graph_edges(&state) {
+        out.push_str(&format!("  n{} -> n{};\n", edge.from, edge.to));
+    }
+    out.push_str("}\n");
+    out
+}
+
+// The editor canvas as a standalone SVG: a box per component in its type color with its label and a
+// short excerpt, and the connection arrows routed and styled as on the canvas. Hidden components are
+// included, like on the canvas.
+pub fn export_diagram_svg() -> String {
+    let state = EDITOR_STATE.read();
+    let mut ids = state.components.keys().copied().collect::<Vec<_>>();
+    ids.sort();
+
+    let bounds = ids.iter().map(|id| &state.components[id]).fold(None, |acc: Option<(f64, f64, f64, f64)>, c| {
+        let (x0, y0, x1, y1) = acc.unwrap_or((c.x, c.y, c.x + c.width, c.y + c.height));
+        Some((x0.min(c.x), y0.min(c.y), x1.max(c.x + c.width), y1.max(c.y + c.height)))
+    });
+    let (min_x, min_y, max_x, max_y) = bounds.unwrap_or((0.0, 0.0, 0.0, 0.0));
+    let (view_x, view_y) = (min_x - DIAGRAM_MARGIN, min_y - DIAGRAM_MARGIN);
+    let (width, height) = (max_x - min_x + 2.0 * DIAGRAM_MARGIN, max_y - min_y + 2.0 * DIAGRAM_MARGIN);
+
+    let mut out = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" viewBox=\"{x} {y} {w} {h}\" font-family=\"system-ui, sans-serif\">\n",
+        x = view_x, y = view_y, w = width, h = height,
+    );
+
+    // one arrowhead marker per arrow color, numbered in order of first use
+    let edges = graph_edges(&state);
+    let mut colors: Vec<String> = Vec::new();
+    for edge in edges.iter() {
+        let color = edge_style(&state, edge.from, edge.to).color;
+        if !colors.contains(&color) {
+            colors.push(color);
+        }
+    }
+    out.push_str("  <defs>\n");
+    for (i, color) in colors.iter().enumerate() {
+        out.push_str(&format!(
+            "    <marker id=\"arrow-{}\" markerWidth=\"10\" markerHeight=\"10\" refX=\"9\" refY=\"3\" orient=\"auto\"><polygon points=\"0 0, 10 3, 0 6\" fill=\"{}\"/></marker>\n",
+            i, escape_html(color),
+        ));
+    }
+    out.push_str("  </defs>\n");
+
+    for edge in edges.iter() {
+        let (Some(parent), Some(child)) = (state.components.get(&edge.from), state.components.get(&edge.to)) else { continue };
+        let start = rect_edge_point_towards(child.x + child.width / 2.0, child.y + child.height / 2.0, parent.x, parent.y, parent.width, parent.height);
+        let end = rect_edge_point_towards(parent.x + parent.width / 2.0, parent.y + parent.height / 2.0, child.x, child.y, child.width, child.height);
+        let style = edge_style(&state, edge.from, edge.to);
+        let marker = colors.iter().position(|c| *c == style.color).unwrap_or(0);
+        let dash = if style.dashed { " stroke-dasharray=\"8 5\"" } else { "" };
+        out.push_str(&format!(
+            "  <polyline points=\"{}\" fill=\"none\" stroke=\"{}\" stroke-width=\"2\"{} marker-end=\"url(#arrow-{})\"/>\n",
+            svg_points(&route_arrow(&state, edge.from, edge.to, start, end)), escape_html(&style.color), dash, marker,
+        ));
+    }
+
+    for id in ids {
+        let c = &state.components[&id];
+        let title = format!("{} #{}", c.component_type.label(), id);
+        let text = c.name.clone().unwrap_or_else(|| plain_text(&c.content));
+        let excerpt = if text.chars().count() > DIAGRAM_EXCERPT_CHARS {
+            format!("{}…", text.chars().take(DIAGRAM_EXCERPT_CHARS).collect::<String>())
+        } else {
+            text
+        };
+        out.push_str(&format!(
+            "  <rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" rx=\"8\" fill=\"{}\" stroke=\"#333\" stroke-width=\"2\"/>\n",
+            c.x, c.y, c.width, c.height, c.component_type.color(),
+        ));
+        out.push_str(&format!(
+            "  <text x=\"{}\" y=\"{}\" fill=\"white\" font-size=\"14\" font-weight=\"bold\">{}</text>\n",
+            c.x + 12.0, c.y + 26.0, escape_html(&title),
+        ));
+        if !excerpt.is_empty() {
+            out.push_str(&format!(
+                "  <text x=\"{}\" y=\"{}\" fill=\"white\" font-size=\"12\">{}</text>\n",
+                c.x + 12.0, c.y + 46.0, escape_html(&excerpt),
+            ));
+        }
+    }
+    out.push_str("</svg>\n");
+    out
+}
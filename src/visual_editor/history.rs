@@ -0,0 +1,156 @@
+use dioxus::prelude::*;
+use std::collections::HashMap;
+use super::component::{Component, EDITOR_STATE};
+use super::connections::EdgeStyle;
+use super::css_reset::CssReset;
+use super::styles_editor::STYLE_EDIT_BUFFER;
+
+const MAX_HISTORY: usize = 100;
+
+// Document part of the editor state; transient UI state (selection, dragging, ...) is not recorded
+#[derive(Clone, Debug)]
+struct Snapshot {
+    components: HashMap<usize, Component>,
+    next_id: usize,
+    page_styles: HashMap<String, String>,
+    css_reset: CssReset,
+    edge_styles: HashMap<(usize, usize), EdgeStyle>,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct History {
+    undo: Vec<Snapshot>,
+    redo: Vec<Snapshot>,
+}
+
+pub static HISTORY: GlobalSignal<History> = Signal::global(History::default);
+
+fn current_snapshot() -> Snapshot {
+    let state = EDITOR_STATE.read();
+    Snapshot {
+        components: state.components.clone(),
+        next_id: state.next_id,
+        page_styles: state.page_styles.clone(),
+        css_reset: state.css_reset.clone(),
+        edge_styles: state.edge_styles.clone(),
+    }
+}
+
+fn restore(snapshot: Snapshot) {
+    let mut state = EDITOR_STATE.write();
+    state.mark_changed();
+    state.components = snapshot.components;
+    state.next_id = snapshot.next_id;
+    state.page_styles = snapshot.page_styles;
+    state.css_reset = snapshot.css_reset;
+    state.edge_styles = snapshot.edge_styles;
+    state.selected_edge = None;
+    let remaining = state.selected_ids.iter().copied().filter(|id| state.components.contains_key(id)).collect();
+    state.selected_ids = remaining;
+    if state.selected_id.is_some_and(|id| !state.components.contains_key(&id)) {
+        state.selected_id = state.selected_ids.last().copied();
+    }
+    // unsaved style edits refer to the old document
+    STYLE_EDIT_BUFFER.write().clear();
+}
+
+// Record the current document so the mutation that follows can be undone as one step
+// (and mark the document as having unsaved changes).
+// Must be called before taking the EDITOR_STATE write lock.
+pub fn push_history() {
+    let snapshot = current_snapshot();
+    EDITOR_STATE.write().mark_changed();
+    let mut history = HISTORY.write();
+    history.undo.push(snapshot);
+    if history.undo.len() > MAX_HISTORY {
+        history.undo.remove(0);
+    }
+    history.redo.clear();
+}
+
+// push_history for an edit made of many small changes, like dragging a slider: only the first change
+// while `in_progress` is unset records a step. Clear `in_progress` when the edit ends (the input's onchange).
+pub fn push_history_once(mut in_progress: Signal<bool>) {
+    if !in_progress() {
+        push_history();
+        in_progress.set(true);
+    }
+}
+
+pub fn undo() {
+    let Some(snapshot) = HISTORY.write().undo.pop() else { return };
+    let current = current_snapshot();
+    HISTORY.write().redo.push(current);
+    restore(snapshot);
+}
+
+pub fn redo() {
+    let Some(snapshot) = HISTORY.write().redo.pop() else { return };
+    let current = current_snapshot();
+    HISTORY.write().undo.push(current);
+    restore(snapshot);
+}
+
+pub fn can_undo() -> bool {
+    !HISTORY.read().undo.is_empty()
+}
+
+pub fn can_redo() -> bool {
+    !HISTORY.read().redo.is_empty()
+}
+
+// (current step, last step) of the timeline made of the undo steps, the current document and the redo steps
+pub fn timeline_position() -> (usize, usize) {
+    let history = HISTORY.read();
+    (history.undo.len(), history.undo.len() + history.redo.len())
+}
+
+// Jump to `step` of the timeline in one go, as that many undos or redos would; the states passed over stay
+// on the stacks, so the redo side is only dropped by the next edit
+pub fn jump_to(step: usize) {
+    let (position, last) = timeline_position();
+    let step = step.min(last);
+    if step == position {
+        return;
+    }
+    let mut current = current_snapshot();
+    {
+        let mut history = HISTORY.write();
+        while history.undo.len() > step {
+            let Some(snapshot) = history.undo.pop() else { break };
+            history.redo.push(std::mem::replace(&mut current, snapshot));
+        }
+        while history.undo.len() < step {
+            let Some(snapshot) = history.redo.pop() else { break };
+            history.undo.push(std::mem::replace(&mut current, snapshot));
+        }
+    }
+    restore(current);
+}
+
+// Slider over the history timeline; the canvas follows while dragging
+#[component]
+pub fn HistoryTimeline() -> Element {
+    let (position, last) = timeline_position();
+    if last == 0 {
+        return rsx! {};
+    }
+
+    rsx! {
+        label { style: "display: flex; align-items: center; gap: 8px; margin-bottom: 16px; font-size: 12px;",
+            title: "Drag through the undo history; editing from an earlier step drops the steps after it",
+            "History"
+            input {
+                r#type: "range",
+                min: "0",
+                max: "{last}",
+                step: "1",
+                style: "flex: 1;",
+                aria_label: "History step",
+                value: "{position}",
+                oninput: move |e| jump_to(e.value().parse().unwrap_or(position)),
+            }
+            "{position}/{last}"
+        }
+    }
+}
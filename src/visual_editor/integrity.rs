@@ -0,0 +1,206 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use super::component::{debug_log, EditorState, EDITOR_STATE};
+
+// What state_integrity_check had to repair; empty when the document was consistent
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct IntegrityReport {
+    // (parent, child) references to components that don't exist
+    pub orphan_refs: Vec<(usize, usize)>,
+    // (parent, child) references listed more than once under the same parent
+    pub duplicate_refs: Vec<(usize, usize)>,
+    // (parent, child) references removed because the child is already under another parent
+    pub shared_refs: Vec<(usize, usize)>,
+    // (parent, child) links removed because they closed a cycle
+    pub broken_cycles: Vec<(usize, usize)>,
+}
+
+impl IntegrityReport {
+    pub fn is_clean(&self) -> bool {
+        self.orphan_refs.is_empty() && self.duplicate_refs.is_empty() && self.shared_refs.is_empty() && self.broken_cycles.is_empty()
+    }
+
+    pub fn fix_count(&self) -> usize {
+        self.orphan_refs.len() + self.duplicate_refs.len() + self.shared_refs.len() + self.broken_cycles.len()
+    }
+}
+
+impl fmt::Display for IntegrityReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut parts = Vec::new();
+        for (parent, child) in self.orphan_refs.iter() {
+            parts.push(format!("removed missing child #{} from #{}", child, parent));
+        }
+        for (parent, child) in self.duplicate_refs.iter() {
+            parts.push(format!("removed duplicate child #{} from #{}", child, parent));
+        }
+        for (parent, child) in self.shared_refs.iter() {
+            parts.push(format!("removed child #{} from #{}, it already has a parent", child, parent));
+        }
+        for (parent, child) in self.broken_cycles.iter() {
+            parts.push(format!("broke cycle at #{} -> #{}", parent, child));
+        }
+        write!(f, "{}", parts.join("; "))
+    }
+}
+
+// Repair the children graph in place: drop references to missing components and repeated entries,
+// keep each child under only one parent (the lowest id), then remove the links that close cycles so the
+// tree can always be rendered
+pub fn repair_state(state: &mut EditorState) -> IntegrityReport {
+    let mut report = IntegrityReport::default();
+    let mut ids = state.components.keys().copied().collect::<Vec<_>>();
+    ids.sort();
+
+    let mut has_parent = HashSet::new();
+    for &id in ids.iter() {
+        let children = state.components[&id].children.clone();
+        let mut kept = Vec::with_capacity(children.len());
+        for child in children {
+            if !state.components.contains_key(&child) {
+                report.orphan_refs.push((id, child));
+            } else if kept.contains(&child) {
+                report.duplicate_refs.push((id, child));
+            } else if !has_parent.insert(child) {
+                report.shared_refs.push((id, child));
+            } else {
+                kept.push(child);
+            }
+        }
+        state.components.get_mut(&id).unwrap().children = kept;
+    }
+
+    // depth-first search; a child that is still on the stack is a back edge and closes a cycle
+    #[derive(Clone, Copy, PartialEq)]
+    enum Mark { OnStack, Done }
+    let mut marks: HashMap<usize, Mark> = HashMap::new();
+    for &root in ids.iter() {
+        if marks.contains_key(&root) {
+            continue;
+        }
+        marks.insert(root, Mark::OnStack);
+        let mut stack = vec![(root, 0usize)];
+        while let Some((id, next)) = stack.last().copied() {
+            let Some(&child) = state.components[&id].children.get(next) else {
+                marks.insert(id, Mark::Done);
+                stack.pop();
+                continue;
+            };
+            match marks.get(&child) {
+                Some(Mark::OnStack) => {
+                    report.broken_cycles.push((id, child));
+                    state.components.get_mut(&id).unwrap().children.remove(next);
+                }
+                Some(Mark::Done) => stack.last_mut().unwrap().1 += 1,
+                None => {
+                    stack.last_mut().unwrap().1 += 1;
+                    marks.insert(child, Mark::OnStack);
+                    stack.push((child, 0));
+                }
+            }
+        }
+    }
+
+    // instances of masters and links to targets that no longer exist are unlinked silently, like stale
+    // arrow styles below
+    for comp in state.components.values_mut() {
+        if comp.master_id.is_some_and(|master| ids.binary_search(&master).is_err()) {
+            comp.master_id = None;
+        }
+        if comp.link_target.is_some_and(|target| ids.binary_search(&target).is_err()) {
+            comp.link_target = None;
+        }
+    }
+
+    // styles of connections that no longer exist are dropped silently
+    let components = &state.components;
+    state.edge_styles.retain(|(parent, child), _| components.get(parent).is_some_and(|c| c.children.contains(child)));
+
+    for &(parent, _) in report.orphan_refs.iter().chain(report.duplicate_refs.iter()).chain(report.shared_refs.iter()).chain(report.broken_cycles.iter()) {
+        if let Some(comp) = state.components.get_mut(&parent) {
+            comp.touch();
+        }
+    }
+    report
+}
+
+// Check and repair the editor's document; returns what was fixed
+pub fn state_integrity_check() -> IntegrityReport {
+    let report = repair_state(&mut EDITOR_STATE.write());
+    if !report.is_clean() {
+        debug_log(|| format!("state_integrity_check: {}", report));
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::visual_editor::component::{Component, ComponentType};
+
+    // `count` loose containers #0..#count, wired up by hand below so the tests can break the tree
+    fn containers(count: usize) -> EditorState {
+        let mut state = EditorState::default();
+        for _ in 0..count {
+            state.document_mut().add(Component::new(0, ComponentType::Container, 0.0, 0.0));
+        }
+        state
+    }
+
+    fn set_children(state: &mut EditorState, id: usize, children: &[usize]) {
+        state.components.get_mut(&id).unwrap().children = children.to_vec();
+    }
+
+    #[test]
+    fn consistent_state_is_left_alone() {
+        let mut state = containers(3);
+        set_children(&mut state, 0, &[1, 2]);
+        assert!(repair_state(&mut state).is_clean());
+        assert_eq!(state.components[&0].children, vec![1, 2]);
+    }
+
+    #[test]
+    fn missing_and_repeated_children_are_dropped() {
+        let mut state = containers(2);
+        set_children(&mut state, 0, &[1, 7, 1]);
+        let report = repair_state(&mut state);
+        assert_eq!(report.orphan_refs, vec![(0, 7)]);
+        assert_eq!(report.duplicate_refs, vec![(0, 1)]);
+        assert_eq!(state.components[&0].children, vec![1]);
+    }
+
+    #[test]
+    fn a_child_keeps_only_its_first_parent() {
+        let mut state = containers(3);
+        set_children(&mut state, 0, &[2]);
+        set_children(&mut state, 1, &[2]);
+        let report = repair_state(&mut state);
+        assert_eq!(report.shared_refs, vec![(1, 2)]);
+        assert_eq!(report.fix_count(), 1);
+        assert_eq!(state.components[&0].children, vec![2]);
+        assert!(state.components[&1].children.is_empty());
+    }
+
+    #[test]
+    fn cycles_are_broken() {
+        let mut state = containers(2);
+        set_children(&mut state, 0, &[1]);
+        set_children(&mut state, 1, &[0]);
+        let report = repair_state(&mut state);
+        assert_eq!(report.broken_cycles, vec![(1, 0)]);
+        assert!(state.components[&1].children.is_empty());
+    }
+
+    #[test]
+    fn dangling_links_and_masters_are_cleared() {
+        let mut state = containers(2);
+        let link = state.document_mut().add(Component::new(0, ComponentType::Link, 0.0, 0.0));
+        state.components.get_mut(&link).unwrap().link_target = Some(9);
+        state.components.get_mut(&link).unwrap().master_id = Some(8);
+        state.components.get_mut(&1).unwrap().link_target = Some(0);
+        repair_state(&mut state);
+        assert_eq!(state.components[&link].link_target, None);
+        assert_eq!(state.components[&link].master_id, None);
+        assert_eq!(state.components[&1].link_target, Some(0));
+    }
+}
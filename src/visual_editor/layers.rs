@@ -0,0 +1,552 @@
+use dioxus::prelude::*;
+use super::component::{rename_component, select_component, toggle_selection, wrap_selection_in_container, ComponentType, EditorState, EDITOR_STATE};
+use super::connections::{can_move, disconnect_components, move_component};
+use super::history::{push_history, push_history_once};
+use super::registry::registered_component_types;
+use super::viewport::center_on;
+use super::integrity::state_integrity_check;
+use super::styles_editor::STYLE_EDIT_BUFFER;
+
+// What a keyboard delete is about to remove
+#[derive(Clone, Debug, PartialEq)]
+pub enum PendingDelete {
+    Components(Vec<usize>),
+    // (parent, child)
+    Connection(usize, usize),
+}
+
+fn apply_delete(target: PendingDelete) {
+    match target {
+        PendingDelete::Components(ids) => delete_components(&ids, false),
+        PendingDelete::Connection(parent, child) => { let _ = disconnect_components(parent, child); }
+    }
+}
+
+// Delete from the keyboard: the selected connection if there is one, otherwise the selected components.
+// Happens immediately, or after DeleteConfirmDialog when `confirm_keyboard_delete` is on.
+// Returns false when there was nothing to delete.
+pub fn request_keyboard_delete() -> bool {
+    let (target, confirm) = {
+        let state = EDITOR_STATE.read();
+        let target = match state.selected_edge {
+            Some((parent, child)) => PendingDelete::Connection(parent, child),
+            None if !state.selected_ids.is_empty() => PendingDelete::Components(state.selected_ids.clone()),
+            None => return false,
+        };
+        (target, state.confirm_keyboard_delete)
+    };
+    if confirm {
+        EDITOR_STATE.write().pending_delete = Some(target);
+    } else {
+        apply_delete(target);
+    }
+    true
+}
+
+pub fn confirm_pending_delete() {
+    let pending = EDITOR_STATE.write().pending_delete.take();
+    if let Some(target) = pending {
+        apply_delete(target);
+    }
+}
+
+pub fn cancel_pending_delete() {
+    EDITOR_STATE.write().pending_delete = None;
+}
+
+// Modal asking to confirm a keyboard delete; Enter confirms and Esc cancels (see handle_shortcut)
+#[component]
+pub fn DeleteConfirmDialog() -> Element {
+    let what = match EDITOR_STATE.read().pending_delete.clone() {
+        None => return rsx! {},
+        Some(PendingDelete::Components(ids)) if ids.len() == 1 => "this component".to_string(),
+        Some(PendingDelete::Components(ids)) => format!("{} components", ids.len()),
+        Some(PendingDelete::Connection(parent, child)) => format!("the connection #{} → #{}", parent, child),
+    };
+
+    rsx! {
+        div {
+            style: "position: fixed; inset: 0; z-index: 100; display: flex; align-items: center; justify-content: center; background: rgba(0,0,0,0.3);",
+            onclick: move |_| cancel_pending_delete(),
+            div {
+                style: "background: white; padding: 16px; border-radius: 6px; font-size: 14px; min-width: 260px;",
+                onclick: move |e| e.stop_propagation(),
+                p { style: "margin: 0 0 12px 0;", "Delete {what}?" }
+                div { style: "display: flex; gap: 8px; justify-content: flex-end;",
+                    button { onclick: move |_| cancel_pending_delete(), "Cancel" }
+                    button {
+                        style: "background: #f44336; color: white; border: none; border-radius: 4px; padding: 4px 12px;",
+                        onclick: move |_| confirm_pending_delete(),
+                        "Delete"
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[derive(Clone, PartialEq)]
+struct LayerRow {
+    id: usize,
+    depth: usize,
+    label: String,
+    name: Option<String>,
+    visible: bool,
+    locked: bool,
+    selected: bool,
+}
+
+// Flatten the component tree (roots in preview order, children in order) into indented rows
+fn layer_rows(state: &EditorState) -> Vec<LayerRow> {
+    fn walk(state: &EditorState, id: usize, depth: usize, rows: &mut Vec<LayerRow>) {
+        // a component listed under several parents is shown once, which also stops cycles
+        if rows.iter().any(|r| r.id == id) {
+            return;
+        }
+        let Some(comp) = state.components.get(&id) else { return };
+        rows.push(LayerRow {
+            id,
+            depth,
+            label: match &comp.name {
+                Some(name) => format!("{} #{} — {}", comp.component_type.label(), id, name),
+                None => format!("{} #{}", comp.component_type.label(), id),
+            },
+            name: comp.name.clone(),
+            visible: comp.visible,
+            locked: comp.locked,
+            selected: state.selected_ids.contains(&id),
+        });
+        for &child in comp.children.iter() {
+            walk(state, child, depth + 1, rows);
+        }
+    }
+
+    let mut rows = Vec::new();
+    for id in state.document().roots() {
+        walk(state, id, 0, &mut rows);
+    }
+    rows
+}
+
+// Where a dragged layer row lands: in the gap above a row (same parent, that row's position),
+// onto a container row (last child), or below all rows (top level)
+#[derive(Clone, Copy, PartialEq)]
+enum LayerDrop {
+    Before(usize),
+    Into(usize),
+    TopLevel,
+}
+
+// Parent and child index a drop resolves to
+fn drop_destination(state: &EditorState, target: LayerDrop) -> (Option<usize>, usize) {
+    match target {
+        LayerDrop::Before(id) => match state.document().parent_of(id) {
+            Some(parent) => (Some(parent), state.components[&parent].children.iter().position(|&c| c == id).unwrap_or(0)),
+            None => (None, 0),
+        },
+        LayerDrop::Into(parent) => (Some(parent), state.components.get(&parent).map_or(0, |c| c.children.len())),
+        LayerDrop::TopLevel => (None, 0),
+    }
+}
+
+// Whether dropping `dragged` on `target` would change anything and keep the tree valid
+fn can_drop(state: &EditorState, dragged: usize, target: LayerDrop) -> bool {
+    match target {
+        LayerDrop::Before(id) if id == dragged => false,
+        LayerDrop::TopLevel => state.document().parent_of(dragged).is_some(),
+        _ => match drop_destination(state, target).0 {
+            Some(parent) => can_move(state, parent, dragged).is_ok(),
+            // top level rows have no stored order, so dropping before one just detaches
+            None => state.document().parent_of(dragged).is_some(),
+        },
+    }
+}
+
+// Accept the drag (and show the indicator) only where the drop is valid
+fn layer_drag_over(e: DragEvent, dragged: Signal<Option<usize>>, mut drop_target: Signal<Option<LayerDrop>>, target: LayerDrop) {
+    let Some(id) = dragged() else { return };
+    if can_drop(&EDITOR_STATE.read(), id, target) {
+        e.prevent_default();
+        if drop_target() != Some(target) {
+            drop_target.set(Some(target));
+        }
+    }
+}
+
+fn layer_drop(e: DragEvent, mut dragged: Signal<Option<usize>>, mut drop_target: Signal<Option<LayerDrop>>, target: LayerDrop) {
+    e.prevent_default();
+    if let Some(id) = dragged() {
+        let (parent, index) = drop_destination(&EDITOR_STATE.read(), target);
+        if let Err(err) = move_component(id, parent, index) {
+            EDITOR_STATE.write().connect_error = Some(err.to_string());
+        }
+    }
+    dragged.set(None);
+    drop_target.set(None);
+}
+
+// Component matching a search: an id ("12" or "#12"), otherwise the lowest id whose type name,
+// name or content contains the text (ignoring case)
+pub fn find_component(state: &EditorState, query: &str) -> Option<usize> {
+    let query = query.trim();
+    if query.is_empty() {
+        return None;
+    }
+    if let Ok(id) = query.trim_start_matches('#').parse::<usize>() {
+        return state.components.contains_key(&id).then_some(id);
+    }
+
+    let needle = query.to_lowercase();
+    state.components.values()
+        .filter(|c| {
+            c.component_type.label().to_lowercase().contains(&needle)
+                || c.name.as_ref().is_some_and(|n| n.to_lowercase().contains(&needle))
+                || c.content.to_lowercase().contains(&needle)
+        })
+        .map(|c| c.id)
+        .min()
+}
+
+// Select the first match for `query` and bring it into view; returns whether anything matched
+pub fn jump_to_component(query: &str) -> bool {
+    let Some(id) = find_component(&EDITOR_STATE.read(), query) else { return false };
+    select_component(id);
+    center_on(id);
+    true
+}
+
+#[component]
+fn JumpToComponent() -> Element {
+    let mut query = use_signal(String::new);
+    let mut not_found = use_signal(|| false);
+    let mut go = move || not_found.set(!jump_to_component(&query()));
+
+    rsx! {
+        div { style: "display: flex; gap: 4px; margin-bottom: 8px;",
+            input {
+                style: "flex: 1; min-width: 0;",
+                placeholder: "Go to id or name",
+                value: "{query}",
+                oninput: move |e| { query.set(e.value()); not_found.set(false); },
+                onkeydown: move |e| {
+                    if e.key() == Key::Enter {
+                        go();
+                    }
+                },
+            }
+            button { onclick: move |_| go(), "Go" }
+        }
+        if not_found() {
+            p { style: "font-size: 12px; color: #666; margin: 0 0 8px 0;", "No matching component" }
+        }
+    }
+}
+
+// Components delete_components removes: the unlocked ones among `ids`, plus with `include_subtree` everything
+// below them. A locked component inside one of those subtrees stops the whole delete rather than being left
+// behind as a root; its id is returned in the error (all of them, sorted).
+fn delete_targets(state: &EditorState, ids: &[usize], include_subtree: bool) -> Result<Vec<usize>, Vec<usize>> {
+    let mut targets = ids.iter().copied()
+        .filter(|id| state.components.get(id).is_some_and(|c| !c.locked))
+        .collect::<Vec<_>>();
+    if include_subtree {
+        let descendants = state.components.values()
+            .filter(|c| targets.iter().any(|&root| state.document().is_descendant(root, c.id)))
+            .collect::<Vec<_>>();
+        let mut locked = descendants.iter().filter(|c| c.locked).map(|c| c.id).collect::<Vec<_>>();
+        if !locked.is_empty() {
+            locked.sort();
+            return Err(locked);
+        }
+        targets.extend(descendants.iter().map(|c| c.id));
+    }
+    targets.sort();
+    targets.dedup();
+    Ok(targets)
+}
+
+// Delete several components as one undoable step. With `include_subtree`, everything below a
+// deleted container goes too; otherwise its children are kept and become roots. Locked components are skipped,
+// and a subtree delete that would take a locked component with it is refused with a message.
+pub fn delete_components(ids: &[usize], include_subtree: bool) {
+    let targets = match delete_targets(&EDITOR_STATE.read(), ids, include_subtree) {
+        Ok(targets) => targets,
+        Err(locked) => {
+            let locked = locked.iter().map(|id| format!("#{}", id)).collect::<Vec<_>>().join(", ");
+            EDITOR_STATE.write().connect_error = Some(format!("Nothing deleted: {} inside the deleted containers is locked", locked));
+            return;
+        }
+    };
+    if targets.is_empty() {
+        return;
+    }
+
+    push_history();
+    {
+        let mut state = EDITOR_STATE.write();
+        for id in targets {
+            state.document_mut().remove(id);
+            STYLE_EDIT_BUFFER.write().remove(&id);
+        }
+    }
+    state_integrity_check();
+}
+
+// Replace the selection with every component of the given type (in id order)
+pub fn select_all_of_type(component_type: &ComponentType) {
+    let mut state = EDITOR_STATE.write();
+    let mut ids = state.components.values()
+        .filter(|c| &c.component_type == component_type)
+        .map(|c| c.id)
+        .collect::<Vec<_>>();
+    ids.sort();
+    state.select(None);
+    state.selected_id = ids.last().copied();
+    state.selected_ids = ids;
+}
+
+pub fn set_visible(ids: &[usize], visible: bool) {
+    push_history();
+    let mut state = EDITOR_STATE.write();
+    for id in ids {
+        if let Some(comp) = state.components.get_mut(id) {
+            comp.visible = visible;
+            comp.touch();
+        }
+    }
+}
+
+pub fn set_locked(ids: &[usize], locked: bool) {
+    push_history();
+    let mut state = EDITOR_STATE.write();
+    for id in ids {
+        if let Some(comp) = state.components.get_mut(id) {
+            comp.locked = locked;
+            comp.touch();
+        }
+    }
+}
+
+#[component]
+pub fn LayersPanel() -> Element {
+    let mut include_subtree = use_signal(|| false);
+    let mut dragged = use_signal(|| None::<usize>);
+    let mut drop_target = use_signal(|| None::<LayerDrop>);
+    // row whose name is being edited inline
+    let mut renaming = use_signal(|| None::<usize>);
+    // set once the rename has recorded its undo step, so a whole rename is undone at once
+    let mut rename_edit = use_signal(|| false);
+    let state = EDITOR_STATE.read();
+    let rows = layer_rows(&state);
+    let selected = state.selected_ids.clone();
+    let any_selected = !selected.is_empty();
+    // toggles switch to the opposite of the first selected component's current value
+    let first = selected.first().and_then(|id| state.components.get(id));
+    let all_hidden = first.is_some_and(|c| !c.visible);
+    let all_locked = first.is_some_and(|c| c.locked);
+    let mut types = ComponentType::BUILT_IN.to_vec();
+    types.extend(registered_component_types().into_iter().map(ComponentType::Custom));
+
+    rsx! {
+        div {
+            JumpToComponent {}
+
+            div { style: "display: flex; flex-wrap: wrap; gap: 4px; margin-bottom: 8px;",
+                button {
+                    disabled: !any_selected,
+                    onclick: {
+                        let selected = selected.clone();
+                        move |_| delete_components(&selected, include_subtree())
+                    },
+                    "Delete"
+                }
+                button {
+                    disabled: !any_selected,
+                    onclick: {
+                        let selected = selected.clone();
+                        move |_| set_visible(&selected, all_hidden)
+                    },
+                    if all_hidden { "Show" } else { "Hide" }
+                }
+                button {
+                    disabled: !any_selected,
+                    onclick: {
+                        let selected = selected.clone();
+                        move |_| set_locked(&selected, !all_locked)
+                    },
+                    if all_locked { "Unlock" } else { "Lock" }
+                }
+                button {
+                    disabled: !any_selected,
+                    onclick: move |_| { wrap_selection_in_container(); },
+                    "Wrap in container"
+                }
+            }
+            div { style: "display: flex; flex-wrap: wrap; align-items: center; gap: 4px; margin-bottom: 8px; font-size: 12px;",
+                "Select all:"
+                for component_type in types {
+                    button {
+                        onclick: {
+                            let component_type = component_type.clone();
+                            move |_| select_all_of_type(&component_type)
+                        },
+                        "{component_type.label()}"
+                    }
+                }
+            }
+            label { style: "font-size: 12px; display: flex; align-items: center; gap: 6px; margin-bottom: 8px;",
+                input {
+                    r#type: "checkbox",
+                    checked: include_subtree(),
+                    onchange: move |e| include_subtree.set(e.checked()),
+                }
+                "Delete containers with their children"
+            }
+
+            if rows.is_empty() {
+                p { style: "font-size: 12px; color: #666; margin: 0;", "No components yet" }
+            }
+            for row in rows {
+                div {
+                    key: "{row.id}",
+                    // gap above the row; shows the insertion line while a row is dragged over it
+                    div {
+                        style: if drop_target() == Some(LayerDrop::Before(row.id)) {
+                            format!("height: 2px; margin: 1px 0 1px {}px; background: #2196F3;", row.depth * 14)
+                        } else {
+                            "height: 4px;".to_string()
+                        },
+                        ondragover: move |e| layer_drag_over(e, dragged, drop_target, LayerDrop::Before(row.id)),
+                        ondragleave: move |_| drop_target.set(None),
+                        ondrop: move |e| layer_drop(e, dragged, drop_target, LayerDrop::Before(row.id)),
+                    }
+                    div {
+                        // text selection in the rename box would otherwise start a row drag
+                        draggable: if renaming() == Some(row.id) { "false" } else { "true" },
+                        style: format!(
+                            "display: flex; align-items: center; gap: 6px; font-size: 12px; padding: 2px 0 2px {}px;{}",
+                            row.depth * 14,
+                            if drop_target() == Some(LayerDrop::Into(row.id)) { " background: #e3f2fd; outline: 1px solid #2196F3;" } else { "" },
+                        ),
+                        ondragstart: move |_| dragged.set(Some(row.id)),
+                        ondragend: move |_| {
+                            dragged.set(None);
+                            drop_target.set(None);
+                        },
+                        ondragover: move |e| layer_drag_over(e, dragged, drop_target, LayerDrop::Into(row.id)),
+                        ondragleave: move |_| drop_target.set(None),
+                        ondrop: move |e| layer_drop(e, dragged, drop_target, LayerDrop::Into(row.id)),
+                        input {
+                            r#type: "checkbox",
+                            checked: row.selected,
+                            onchange: move |_| toggle_selection(row.id),
+                        }
+                        if renaming() == Some(row.id) {
+                            input {
+                                r#type: "text",
+                                style: "flex: 1; font-size: 12px;",
+                                autofocus: true,
+                                placeholder: "Name",
+                                value: "{row.name.clone().unwrap_or_default()}",
+                                oninput: move |e| {
+                                    push_history_once(rename_edit);
+                                    rename_component(row.id, e.value());
+                                },
+                                onkeydown: move |e| {
+                                    if e.key() == Key::Enter || e.key() == Key::Escape {
+                                        renaming.set(None);
+                                        rename_edit.set(false);
+                                    }
+                                },
+                                onblur: move |_| {
+                                    renaming.set(None);
+                                    rename_edit.set(false);
+                                },
+                            }
+                        } else {
+                            span {
+                                style: if row.visible { "flex: 1; cursor: pointer;" } else { "flex: 1; cursor: pointer; opacity: 0.5;" },
+                                title: "Double-click to rename",
+                                onclick: move |_| select_component(row.id),
+                                ondoubleclick: move |_| renaming.set(Some(row.id)),
+                                "{row.label}"
+                            }
+                        }
+                        span {
+                            style: "cursor: pointer;",
+                            title: if row.visible { "Hide" } else { "Show" },
+                            onclick: move |_| set_visible(&[row.id], !row.visible),
+                            if row.visible { "👁" } else { "🚫" }
+                        }
+                        span {
+                            style: if row.locked { "cursor: pointer;" } else { "cursor: pointer; opacity: 0.4;" },
+                            title: if row.locked { "Unlock" } else { "Lock" },
+                            onclick: move |_| set_locked(&[row.id], !row.locked),
+                            if row.locked { "🔒" } else { "🔓" }
+                        }
+                    }
+                }
+            }
+            if dragged().is_some() {
+                div {
+                    style: if drop_target() == Some(LayerDrop::TopLevel) {
+                        "margin-top: 4px; padding: 4px; font-size: 11px; color: #2196F3; border: 1px dashed #2196F3;"
+                    } else {
+                        "margin-top: 4px; padding: 4px; font-size: 11px; color: #999; border: 1px dashed #ccc;"
+                    },
+                    ondragover: move |e| layer_drag_over(e, dragged, drop_target, LayerDrop::TopLevel),
+                    ondragleave: move |_| drop_target.set(None),
+                    ondrop: move |e| layer_drop(e, dragged, drop_target, LayerDrop::TopLevel),
+                    "Drop here to move to the top level"
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::visual_editor::component::Component;
+
+    // Container #0 > [Container #1 > Heading #2, Paragraph #3]
+    fn tree() -> EditorState {
+        let mut state = EditorState::default();
+        let mut doc = state.document_mut();
+        let outer = doc.add(Component::new(0, ComponentType::Container, 0.0, 0.0));
+        let inner = doc.add(Component::new(0, ComponentType::Container, 0.0, 100.0));
+        let heading = doc.add(Component::new(0, ComponentType::Heading, 0.0, 200.0));
+        let paragraph = doc.add(Component::new(0, ComponentType::Paragraph, 200.0, 100.0));
+        doc.connect(outer, inner).unwrap();
+        doc.connect(inner, heading).unwrap();
+        doc.connect(outer, paragraph).unwrap();
+        state
+    }
+
+    fn lock(state: &mut EditorState, id: usize) {
+        state.components.get_mut(&id).unwrap().locked = true;
+    }
+
+    #[test]
+    fn subtree_delete_takes_every_descendant() {
+        assert_eq!(delete_targets(&tree(), &[0], true), Ok(vec![0, 1, 2, 3]));
+        assert_eq!(delete_targets(&tree(), &[0], false), Ok(vec![0]));
+    }
+
+    #[test]
+    fn locked_descendant_refuses_a_subtree_delete() {
+        let mut state = tree();
+        lock(&mut state, 2);
+        lock(&mut state, 3);
+        assert_eq!(delete_targets(&state, &[0], true), Err(vec![2, 3]));
+        // without the subtree nothing locked is touched
+        assert_eq!(delete_targets(&state, &[0], false), Ok(vec![0]));
+    }
+
+    #[test]
+    fn locked_selection_is_skipped_with_its_subtree() {
+        let mut state = tree();
+        lock(&mut state, 1);
+        assert_eq!(delete_targets(&state, &[1, 3], true), Ok(vec![3]));
+    }
+}
@@ -0,0 +1,112 @@
+use dioxus::prelude::*;
+use std::collections::HashMap;
+use super::component::{child_gap, round_coord, EditorState, EDITOR_STATE};
+use super::history::push_history;
+
+// Spacing used by the tidy layout, in canvas pixels
+const MARGIN: f64 = 40.0;
+pub const SIBLING_GAP: f64 = 40.0;
+pub const LEVEL_GAP: f64 = 60.0;
+
+// Tree layout of the connection graph: parents centered above their children, siblings side by side
+// (SIBLING_GAP apart, or the container's own child gap when it sets one) and root trees in a row. Returns the new top-left position of every component.
+// A component listed under several containers is placed under the first one reached; components only
+// reachable through a cycle start extra trees.
+pub fn tidy_positions(state: &EditorState) -> HashMap<usize, (f64, f64)> {
+    // spanning forest in document order
+    let mut ids = state.components.keys().copied().collect::<Vec<_>>();
+    ids.sort();
+    let mut roots = Vec::new();
+    let mut tree: HashMap<usize, Vec<usize>> = HashMap::new();
+    let mut depth: HashMap<usize, usize> = HashMap::new();
+    for root in state.document().roots().into_iter().chain(ids) {
+        if depth.contains_key(&root) {
+            continue;
+        }
+        roots.push(root);
+        depth.insert(root, 0);
+        let mut stack = vec![root];
+        while let Some(id) = stack.pop() {
+            let mut kids = Vec::new();
+            for &child in state.components[&id].children.iter() {
+                if state.components.contains_key(&child) && !depth.contains_key(&child) {
+                    depth.insert(child, depth[&id] + 1);
+                    kids.push(child);
+                }
+            }
+            stack.extend(kids.iter().rev());
+            tree.insert(id, kids);
+        }
+    }
+
+    // each level is as tall as its tallest box
+    let levels = depth.values().max().map_or(0, |d| d + 1);
+    let mut level_y = vec![MARGIN; levels];
+    for level in 1..levels {
+        let tallest = depth.iter()
+            .filter(|&(_, &d)| d == level - 1)
+            .map(|(id, _)| state.components[id].height)
+            .fold(0.0, f64::max);
+        level_y[level] = level_y[level - 1] + tallest + LEVEL_GAP;
+    }
+
+    fn sibling_gap(state: &EditorState, id: usize) -> f64 {
+        child_gap(&state.components[&id]).unwrap_or(SIBLING_GAP)
+    }
+
+    fn span(state: &EditorState, tree: &HashMap<usize, Vec<usize>>, id: usize, spans: &mut HashMap<usize, f64>) -> f64 {
+        let kids = &tree[&id];
+        let children = kids.iter().map(|&k| span(state, tree, k, spans)).sum::<f64>()
+            + sibling_gap(state, id) * kids.len().saturating_sub(1) as f64;
+        let width = state.components[&id].width.max(children);
+        spans.insert(id, width);
+        width
+    }
+
+    // each subtree gets its span; the parent is centered over it and its children share it left to right
+    let mut spans = HashMap::new();
+    let mut stack = Vec::new();
+    let mut left = MARGIN;
+    for root in roots {
+        let width = span(state, &tree, root, &mut spans);
+        stack.push((root, left, 0));
+        left += width + SIBLING_GAP;
+    }
+    let mut positions = HashMap::new();
+    while let Some((id, left, level)) = stack.pop() {
+        let width = state.components[&id].width;
+        positions.insert(id, (left + (spans[&id] - width) / 2.0, level_y[level]));
+
+        let kids = &tree[&id];
+        let gap = sibling_gap(state, id);
+        let children = kids.iter().map(|k| spans[k]).sum::<f64>() + gap * kids.len().saturating_sub(1) as f64;
+        let mut x = left + (spans[&id] - children) / 2.0;
+        for &kid in kids {
+            stack.push((kid, x, level + 1));
+            x += spans[&kid] + gap;
+        }
+    }
+    positions
+}
+
+// Move every component to its tidy_positions spot as one undoable step
+pub fn tidy_layout() {
+    let positions = tidy_positions(&EDITOR_STATE.read());
+    if positions.is_empty() {
+        return;
+    }
+
+    push_history();
+    let mut state = EDITOR_STATE.write();
+    let precision = state.coord_precision;
+    for (id, (x, y)) in positions {
+        if let Some(comp) = state.components.get_mut(&id) {
+            let (x, y) = (round_coord(x, precision), round_coord(y, precision));
+            if comp.x != x || comp.y != y {
+                comp.x = x;
+                comp.y = y;
+                comp.touch();
+            }
+        }
+    }
+}
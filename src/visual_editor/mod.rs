@@ -1,3 +1,26 @@
 pub mod styles_editor;
 pub mod component;
-
+pub mod export;
+pub mod history;
+pub mod find_replace;
+pub mod shortcuts;
+pub mod registry;
+pub mod connections;
+pub mod project;
+pub mod layers;
+pub mod graph;
+pub mod viewport;
+pub mod toolbox;
+pub mod integrity;
+pub mod snippets;
+pub mod symbols;
+pub mod layout;
+pub mod contrast;
+pub mod rich_text;
+pub mod routing;
+pub mod document;
+pub mod gallery;
+pub mod anchors;
+pub mod type_defaults;
+pub mod css_reset;
+pub mod bundle;
@@ -0,0 +1,423 @@
+use dioxus::prelude::*;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::cell::Cell;
+use std::collections::{BTreeMap, HashMap};
+use std::rc::Rc;
+use super::component::{Component, EditorState, WindowListener, BOX_WIDTH, EDITOR_STATE};
+use super::connections::EdgeStyle;
+use super::css_reset::CssReset;
+use super::history::push_history;
+use super::integrity::{repair_state, IntegrityReport};
+use super::integrity::state_integrity_check;
+use super::snippets::{collect_subtree, insert_subtree, Snippet, SNIPPET_LIBRARY};
+use super::styles_editor::STYLE_EDIT_BUFFER;
+
+// Format version written by export_state_json. Bump it (and add a migration step) whenever a saved
+// field changes meaning or a new one needs a value other than its serde default.
+pub const PROJECT_VERSION: u64 = 2;
+
+// Quiet period after the last document change before VisualEditor's `on_change` fires
+#[cfg(target_arch = "wasm32")]
+const CHANGE_DEBOUNCE_MS: i32 = 500;
+
+// Saved project: the whole document, including editor-only data such as notes
+#[derive(Serialize, Deserialize)]
+struct ProjectFile {
+    version: u64,
+    components: Vec<Component>,
+    next_id: usize,
+    #[serde(default)]
+    page_styles: HashMap<String, String>,
+    #[serde(default)]
+    css_reset: CssReset,
+    #[serde(default)]
+    edge_styles: Vec<SavedEdgeStyle>,
+    #[serde(default)]
+    snippets: BTreeMap<String, Snippet>,
+}
+
+// Arrow style for one connection (JSON objects can't be keyed by (parent, child) pairs)
+#[derive(Serialize, Deserialize)]
+struct SavedEdgeStyle {
+    from: usize,
+    to: usize,
+    #[serde(flatten)]
+    style: EdgeStyle,
+}
+
+// Serialize the document to JSON (components ordered by id so saves diff cleanly)
+pub fn export_state_json() -> String {
+    let state = EDITOR_STATE.read();
+    let mut components = state.components.values().cloned().collect::<Vec<_>>();
+    components.sort_by_key(|c| c.id);
+
+    let mut edge_styles = state.edge_styles.iter()
+        .map(|(&(from, to), style)| SavedEdgeStyle { from, to, style: style.clone() })
+        .collect::<Vec<_>>();
+    edge_styles.sort_by_key(|e| (e.from, e.to));
+
+    let project = ProjectFile {
+        version: PROJECT_VERSION,
+        components,
+        next_id: state.next_id,
+        page_styles: state.page_styles.clone(),
+        css_reset: state.css_reset.clone(),
+        edge_styles,
+        snippets: SNIPPET_LIBRARY.read().iter().map(|(name, s)| (name.clone(), s.clone())).collect(),
+    };
+    serde_json::to_string_pretty(&project).unwrap_or_default()
+}
+
+// Upgrade a saved project to PROJECT_VERSION, one version step at a time.
+// Files without a version field are version 1; files from a newer editor are rejected.
+pub fn migrate_project(mut project: Value) -> Result<Value, serde_json::Error> {
+    let version = project.get("version").and_then(Value::as_u64).unwrap_or(1);
+    if version > PROJECT_VERSION {
+        return Err(serde::de::Error::custom(format!(
+            "project version {} is newer than this editor supports ({})",
+            version, PROJECT_VERSION
+        )));
+    }
+    if version < 2 {
+        project = migrate_v1_to_v2(project);
+    }
+    // anything that isn't an object is left for ProjectFile deserialization to reject
+    if let Some(obj) = project.as_object_mut() {
+        obj.insert("version".to_string(), PROJECT_VERSION.into());
+    }
+    Ok(project)
+}
+
+// v1 predates the editor-only component fields; fill them in and rename the container `label` to `name`
+fn migrate_v1_to_v2(mut project: Value) -> Value {
+    if let Some(components) = project.get_mut("components").and_then(Value::as_array_mut) {
+        for comp in components.iter_mut().filter_map(Value::as_object_mut) {
+            if let Some(label) = comp.remove("label") {
+                comp.entry("name").or_insert(label);
+            }
+            comp.entry("visible").or_insert(true.into());
+            comp.entry("locked").or_insert(false.into());
+            comp.entry("width").or_insert(BOX_WIDTH.into());
+        }
+    }
+    project
+}
+
+// A saved project of any supported version, migrated to PROJECT_VERSION
+fn parse_project(json: &str) -> Result<ProjectFile, serde_json::Error> {
+    serde_json::from_value(migrate_project(serde_json::from_str(json)?)?)
+}
+
+// One component and everything below it, for copying structure between projects
+#[derive(Serialize, Deserialize)]
+struct SubtreeFile {
+    version: u64,
+    #[serde(flatten)]
+    tree: Snippet,
+}
+
+// Self-contained JSON for component `root_id` and its subtree, with ids renumbered from 0 (the root).
+// Symbol links to masters and Link targets outside the subtree are dropped since they wouldn't mean anything elsewhere.
+pub fn export_subtree(root_id: usize) -> Option<String> {
+    subtree_json(&EDITOR_STATE.read(), root_id)
+}
+
+fn subtree_json(state: &EditorState, root_id: usize) -> Option<String> {
+    let mut tree = collect_subtree(state, root_id)?;
+    let relative = tree.components.iter()
+        .enumerate()
+        .map(|(i, c)| (c.id, i))
+        .collect::<HashMap<_, _>>();
+    for comp in tree.components.iter_mut() {
+        comp.id = relative[&comp.id];
+        comp.children = comp.children.iter().filter_map(|child| relative.get(child).copied()).collect();
+        comp.master_id = comp.master_id.and_then(|master| relative.get(&master).copied());
+        comp.link_target = comp.link_target.and_then(|target| relative.get(&target).copied());
+    }
+    for (parent, child, _) in tree.edge_styles.iter_mut() {
+        *parent = relative[parent];
+        *child = relative[child];
+    }
+    tree.edge_styles.sort_by_key(|&(parent, child, _)| (parent, child));
+    tree.root = 0;
+    serde_json::to_string_pretty(&SubtreeFile { version: PROJECT_VERSION, tree }).ok()
+}
+
+// Insert a subtree produced by export_subtree (any project) as one undoable step; its ids are rebased
+// onto next_id and it lands in the middle of the view. Returns the new root's id.
+pub fn import_subtree(json: &str) -> Result<usize, serde_json::Error> {
+    let root = insert_subtree(parse_subtree(json)?, None)
+        .ok_or_else(|| serde::de::Error::custom("the subtree's root component is missing"))?;
+    state_integrity_check();
+    Ok(root)
+}
+
+fn parse_subtree(json: &str) -> Result<Snippet, serde_json::Error> {
+    let file: SubtreeFile = serde_json::from_value(migrate_project(serde_json::from_str(json)?)?)?;
+    Ok(file.tree)
+}
+
+// Replace the document with a project previously produced by export_state_json (undoable).
+// Older project versions are migrated; broken references in the file are repaired and reported rather than rejected.
+pub fn import_state_json(json: &str) -> Result<IntegrityReport, serde_json::Error> {
+    let project = parse_project(json)?;
+
+    push_history();
+    let mut state = EDITOR_STATE.write();
+    let max_id = project.components.iter().map(|c| c.id + 1).max().unwrap_or(0);
+    state.components = project.components.into_iter().map(|c| (c.id, c)).collect();
+    state.next_id = project.next_id.max(max_id);
+    state.page_styles = project.page_styles;
+    state.css_reset = project.css_reset;
+    state.edge_styles = project.edge_styles.into_iter().map(|e| ((e.from, e.to), e.style)).collect();
+    let report = repair_state(&mut state);
+    state.select(None);
+    // the imported project is what's saved
+    state.dirty = false;
+    STYLE_EDIT_BUFFER.write().clear();
+    // snippets from the file are added to the library (same names are replaced), keeping the user's others
+    SNIPPET_LIBRARY.write().extend(project.snippets);
+    Ok(report)
+}
+
+// Warn before closing/reloading the tab while there are unsaved changes (wasm only), until the returned
+// guard is dropped. The check runs at unload time so `warn_on_unload` can be switched off later,
+// e.g. when the document is persisted some other way.
+pub fn install_unload_guard() -> WindowListener {
+    #[cfg(target_arch = "wasm32")]
+    {
+        use wasm_bindgen::JsCast;
+        WindowListener::new("beforeunload", |e| {
+            let Ok(e) = e.dyn_into::<web_sys::BeforeUnloadEvent>() else { return };
+            let state = EDITOR_STATE.read();
+            if state.warn_on_unload && state.dirty {
+                // browsers show their own generic message; the return value just has to be set
+                e.prevent_default();
+                e.set_return_value("unsaved changes");
+            }
+        })
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        WindowListener::default()
+    }
+}
+
+// Hook behind VisualEditor's `on_change`: calls it with export_state_json() after the document changes,
+// i.e. after EditorState::mark_changed bumped the revision. Mutations count whatever caused them (editing,
+// undo/redo, imports, snippets); selection, hovering, zoom/pan, measured box sizes and an in-progress drag
+// or connection don't, and a drag counts once it's dropped.
+// Bursts of changes fire once, CHANGE_DEBOUNCE_MS after the last one (non-web targets call right away).
+// Nothing fires for the document the editor mounted with, or after it unmounts.
+pub fn use_change_callback(on_change: Option<EventHandler<String>>) {
+    // bumped by every change (and on unmount) so only the latest pending call goes through
+    let generation = use_hook(|| Rc::new(Cell::new(0u64)));
+    let revision = use_memo(move || -> Option<u64> {
+        let state = EDITOR_STATE.read();
+        // dragged boxes move on every mousemove; wait for the drop
+        if on_change.is_none() || state.dragging_id.is_some() {
+            return None;
+        }
+        Some(state.revision)
+    });
+    let last_seen = use_hook(|| Rc::new(Cell::new(*revision.peek())));
+
+    use_effect({
+        let generation = generation.clone();
+        move || {
+            let Some(current) = revision() else { return };
+            if last_seen.get() == Some(current) {
+                return;
+            }
+            last_seen.set(Some(current));
+            let Some(on_change) = on_change else { return };
+            let ticket = generation.get() + 1;
+            generation.set(ticket);
+
+            #[cfg(target_arch = "wasm32")]
+            {
+                use wasm_bindgen::JsCast;
+                let generation = generation.clone();
+                // timers run outside the Dioxus runtime; export_state_json needs it to read the state
+                let fire = Runtime::wrap_closure(move |()| {
+                    if generation.get() == ticket {
+                        on_change.call(export_state_json());
+                    }
+                });
+                if let Some(window) = web_sys::window() {
+                    let callback = wasm_bindgen::prelude::Closure::once_into_js(move || fire(()));
+                    let _ = window.set_timeout_with_callback_and_timeout_and_arguments_0(callback.unchecked_ref(), CHANGE_DEBOUNCE_MS);
+                }
+            }
+
+            #[cfg(not(target_arch = "wasm32"))]
+            {
+                let _ = ticket;
+                on_change.call(export_state_json());
+            }
+        }
+    });
+    use_drop(move || generation.set(generation.get() + 1));
+}
+
+#[component]
+pub fn ImportPanel() -> Element {
+    let mut json = use_signal(String::new);
+    let mut error = use_signal(|| None::<String>);
+    let mut repaired = use_signal(|| None::<String>);
+
+    rsx! {
+        div { style: "display: flex; flex-direction: column; gap: 8px;",
+            textarea {
+                rows: "4",
+                placeholder: "Paste project JSON to import",
+                style: "width: 100%; box-sizing: border-box; font-family: monospace; font-size: 11px;",
+                value: "{json}",
+                oninput: move |e| json.set(e.value()),
+            }
+            button {
+                disabled: json().trim().is_empty(),
+                onclick: move |_| match import_state_json(&json()) {
+                    Ok(report) => {
+                        json.set(String::new());
+                        error.set(None);
+                        repaired.set((!report.is_clean()).then(|| format!("Repaired {} issue(s): {}", report.fix_count(), report)));
+                    }
+                    Err(e) => { error.set(Some(e.to_string())); repaired.set(None); }
+                },
+                "Import JSON"
+            }
+            button {
+                disabled: json().trim().is_empty(),
+                title: "Add a subtree exported from any project without replacing this one",
+                onclick: move |_| match import_subtree(&json()) {
+                    Ok(_) => {
+                        json.set(String::new());
+                        error.set(None);
+                        repaired.set(None);
+                    }
+                    Err(e) => { error.set(Some(e.to_string())); repaired.set(None); }
+                },
+                "Import as subtree"
+            }
+            if let Some(err) = error() {
+                p { class: "text-danger", style: "font-size: 12px; margin: 0;", "{err}" }
+            }
+            if let Some(msg) = repaired() {
+                p { style: "font-size: 12px; color: #666; margin: 0;", "{msg}" }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::visual_editor::component::ComponentType;
+    use crate::visual_editor::snippets::rebase_into;
+    use serde_json::json;
+
+    // v1 files have no version field, call the name `label` and lack the later editor-only fields
+    fn v1_project() -> Value {
+        json!({
+            "components": [
+                { "id": 0, "component_type": "Container", "children": [1], "styles": {}, "content": "",
+                  "x": 0.0, "y": 0.0, "height": 60.0, "created_at": 0.0, "updated_at": 0.0, "label": "Hero" },
+                { "id": 1, "component_type": "Heading", "children": [], "styles": { "color": "red" }, "content": "Hi",
+                  "x": 0.0, "y": 100.0, "height": 60.0, "created_at": 0.0, "updated_at": 0.0 }
+            ],
+            "next_id": 2
+        })
+    }
+
+    #[test]
+    fn v1_is_migrated_to_the_current_shape() {
+        let migrated = migrate_project(v1_project()).unwrap();
+        assert_eq!(migrated["version"], json!(PROJECT_VERSION));
+        let hero = &migrated["components"][0];
+        assert_eq!(hero["name"], json!("Hero"));
+        assert!(hero.get("label").is_none());
+        assert_eq!(hero["visible"], json!(true));
+        assert_eq!(hero["locked"], json!(false));
+        assert_eq!(hero["width"], json!(BOX_WIDTH));
+    }
+
+    #[test]
+    fn v1_file_parses_as_a_project() {
+        let project = parse_project(&v1_project().to_string()).unwrap();
+        assert_eq!(project.version, PROJECT_VERSION);
+        assert_eq!(project.next_id, 2);
+        assert_eq!(project.components[0].name.as_deref(), Some("Hero"));
+        assert_eq!(project.components[0].children, vec![1]);
+        assert_eq!(project.components[1].styles.get("color").map(String::as_str), Some("red"));
+        assert!(project.components.iter().all(|c| c.visible && !c.locked));
+    }
+
+    #[test]
+    fn v1_label_does_not_replace_an_existing_name() {
+        let mut project = v1_project();
+        project["components"][0]["name"] = json!("Kept");
+        let migrated = migrate_project(project).unwrap();
+        assert_eq!(migrated["components"][0]["name"], json!("Kept"));
+    }
+
+    #[test]
+    fn current_version_passes_unchanged() {
+        let current = migrate_project(v1_project()).unwrap();
+        assert_eq!(migrate_project(current.clone()).unwrap(), current);
+    }
+
+    #[test]
+    fn newer_version_is_rejected() {
+        let mut project = migrate_project(v1_project()).unwrap();
+        project["version"] = json!(PROJECT_VERSION + 1);
+        let err = migrate_project(project.clone()).unwrap_err();
+        assert!(err.to_string().contains("newer than this editor supports"));
+        assert!(parse_project(&project.to_string()).is_err());
+    }
+
+    // Heading #0, then Container #1 > [Link #2 -> Heading #3, Heading #3, Link #4 -> Heading #0]
+    fn linked_subtree() -> EditorState {
+        let mut state = EditorState::default();
+        let mut doc = state.document_mut();
+        let outside = doc.add(Component::new(0, ComponentType::Heading, 400.0, 0.0));
+        let container = doc.add(Component::new(0, ComponentType::Container, 0.0, 0.0));
+        let link = doc.add(Component::new(0, ComponentType::Link, 0.0, 100.0));
+        let heading = doc.add(Component::new(0, ComponentType::Heading, 0.0, 200.0));
+        let stray = doc.add(Component::new(0, ComponentType::Link, 0.0, 300.0));
+        doc.connect(container, link).unwrap();
+        doc.connect(container, heading).unwrap();
+        doc.connect(container, stray).unwrap();
+        state.components.get_mut(&link).unwrap().link_target = Some(heading);
+        state.components.get_mut(&stray).unwrap().link_target = Some(outside);
+        state
+    }
+
+    #[test]
+    fn exported_links_use_subtree_ids() {
+        let tree = parse_subtree(&subtree_json(&linked_subtree(), 1).unwrap()).unwrap();
+        let link = tree.components.iter().find(|c| c.component_type == ComponentType::Link && c.link_target.is_some()).unwrap();
+        let target = tree.components.iter().find(|c| Some(c.id) == link.link_target).unwrap();
+        assert_eq!(target.component_type, ComponentType::Heading);
+        // the target outside the subtree is dropped
+        assert_eq!(tree.components.iter().filter(|c| c.link_target.is_some()).count(), 1);
+    }
+
+    #[test]
+    fn imported_links_point_at_the_imported_target() {
+        let json = subtree_json(&linked_subtree(), 1).unwrap();
+        // a destination whose ids overlap the file's relative ids
+        let mut state = linked_subtree();
+        let root = rebase_into(&mut state, parse_subtree(&json).unwrap(), None, (0.0, 0.0)).unwrap();
+
+        let copied = collect_subtree(&state, root).unwrap().components.iter().map(|c| c.id).collect::<Vec<_>>();
+        let links = copied.iter().map(|id| &state.components[id]).filter(|c| c.component_type == ComponentType::Link).collect::<Vec<_>>();
+        assert_eq!(links.len(), 2);
+        let target = links.iter().find_map(|c| c.link_target).unwrap();
+        assert!(copied.contains(&target));
+        assert_eq!(state.components[&target].component_type, ComponentType::Heading);
+        assert!(links.iter().filter(|c| c.link_target != Some(target)).all(|c| c.link_target.is_none()));
+    }
+}
@@ -0,0 +1,69 @@
+use dioxus::prelude::*;
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::rc::Rc;
+use super::component::Component;
+use super::export::escape_html;
+
+// Renders a custom component type (ComponentType::Custom) in the editor, preview and export, e.g. a highlighted note:
+//
+//     struct CalloutRenderer;
+//
+//     impl ComponentRenderer for CalloutRenderer {
+//         fn preview(&self, component: &Component) -> Element {
+//             rsx! { aside { class: "callout", "{component.content}" } }
+//         }
+//
+//         fn export(&self, component: &Component) -> String {
+//             format!("<aside class=\"callout\">{}</aside>", escape_html(&component.content))
+//         }
+//     }
+//
+//     register_component_type("Callout", CalloutRenderer);
+pub trait ComponentRenderer {
+    fn preview(&self, component: &Component) -> Element;
+    // HTML placed inside the component's exported wrapper div (which carries its class/styles)
+    fn export(&self, component: &Component) -> String;
+    // Short text shown on the component's box in the editor canvas
+    fn editor_summary(&self, component: &Component) -> String {
+        component.content.clone()
+    }
+}
+
+// Fallback for custom types without a registered renderer: plain div with the content
+pub struct DefaultRenderer;
+
+impl ComponentRenderer for DefaultRenderer {
+    fn preview(&self, component: &Component) -> Element {
+        rsx! { div { "{component.content}" } }
+    }
+
+    fn export(&self, component: &Component) -> String {
+        format!("<div>{}</div>", escape_html(&component.content))
+    }
+}
+
+// Plain thread-local rather than a GlobalSignal so types can be registered before the dioxus runtime exists
+thread_local! {
+    static COMPONENT_REGISTRY: RefCell<BTreeMap<String, Rc<dyn ComponentRenderer>>> = RefCell::new(BTreeMap::new());
+}
+
+// Register (or replace) the renderer for a custom component type name.
+// Call before mounting `VisualEditor`; registered types are listed in the toolbox.
+// Embedding API: the app itself registers no custom types
+#[allow(dead_code)]
+pub fn register_component_type<R: ComponentRenderer + 'static>(name: impl Into<String>, renderer: R) {
+    COMPONENT_REGISTRY.with(|r| r.borrow_mut().insert(name.into(), Rc::new(renderer)));
+}
+
+// Renderer for a custom type name, falling back to DefaultRenderer when none is registered
+pub fn renderer_for(name: &str) -> Rc<dyn ComponentRenderer> {
+    COMPONENT_REGISTRY
+        .with(|r| r.borrow().get(name).cloned())
+        .unwrap_or_else(|| Rc::new(DefaultRenderer))
+}
+
+// Registered type names in stable (alphabetical) order, used to build the toolbox
+pub fn registered_component_types() -> Vec<String> {
+    COMPONENT_REGISTRY.with(|r| r.borrow().keys().cloned().collect())
+}
@@ -0,0 +1,156 @@
+use dioxus::prelude::*;
+use super::component::{ComponentType, EDITOR_STATE};
+use super::export::escape_html;
+use super::symbols::sync_instances;
+
+// Inline formatting tags that Heading/Paragraph content may carry; anything else in the content is text
+pub const FORMAT_TAGS: [&str; 3] = ["strong", "em", "u"];
+
+pub fn supports_formatting(component_type: &ComponentType) -> bool {
+    matches!(component_type, ComponentType::Heading | ComponentType::Paragraph)
+}
+
+// The formatting tag starting at the beginning of `text`, as (tag, is_closing, length in bytes)
+pub fn tag_at(text: &str) -> Option<(&'static str, bool, usize)> {
+    FORMAT_TAGS.iter().find_map(|&tag| {
+        if text.starts_with(&format!("<{}>", tag)) {
+            Some((tag, false, tag.len() + 2))
+        } else if text.starts_with(&format!("</{}>", tag)) {
+            Some((tag, true, tag.len() + 3))
+        } else {
+            None
+        }
+    })
+}
+
+// Content as safe HTML: the formatting tags are kept (stray closing tags dropped, unclosed ones closed at
+// the end) and everything else is escaped, so typed `<`/`&` still show up as text
+pub fn inline_html(content: &str) -> String {
+    let mut out = String::new();
+    let mut open: Vec<&str> = Vec::new();
+    let mut rest = content;
+    while let Some(c) = rest.chars().next() {
+        match tag_at(rest) {
+            Some((tag, closing, len)) => {
+                if !closing {
+                    open.push(tag);
+                    out.push_str(&rest[..len]);
+                } else if open.last() == Some(&tag) {
+                    open.pop();
+                    out.push_str(&rest[..len]);
+                }
+                rest = &rest[len..];
+            }
+            None => {
+                out.push_str(&escape_html(&c.to_string()));
+                rest = &rest[c.len_utf8()..];
+            }
+        }
+    }
+    for tag in open.into_iter().rev() {
+        out.push_str(&format!("</{}>", tag));
+    }
+    out
+}
+
+// Content with the formatting tags removed, for the editor boxes
+pub fn plain_text(content: &str) -> String {
+    let mut out = String::new();
+    let mut rest = content;
+    while let Some(c) = rest.chars().next() {
+        match tag_at(rest) {
+            Some((_, _, len)) => rest = &rest[len..],
+            None => {
+                out.push(c);
+                rest = &rest[c.len_utf8()..];
+            }
+        }
+    }
+    out
+}
+
+// Byte offset of a UTF-16 offset (what the DOM reports for input selections)
+fn byte_offset(text: &str, utf16: usize) -> usize {
+    let mut units = 0;
+    for (i, c) in text.char_indices() {
+        if units >= utf16 {
+            return i;
+        }
+        units += c.len_utf16();
+    }
+    text.len()
+}
+
+// Wrap content[start..end] (UTF-16 offsets) in `tag`, or unwrap it when it's already wrapped exactly.
+// None for an empty selection.
+pub fn toggle_format(content: &str, start: usize, end: usize, tag: &str) -> Option<String> {
+    let (start, end) = (byte_offset(content, start.min(end)), byte_offset(content, start.max(end)));
+    if start == end {
+        return None;
+    }
+    let (open, close) = (format!("<{}>", tag), format!("</{}>", tag));
+    let (before, selected, after) = (&content[..start], &content[start..end], &content[end..]);
+    if let (Some(before), Some(after)) = (before.strip_suffix(&open), after.strip_prefix(&close)) {
+        return Some(format!("{}{}{}", before, selected, after));
+    }
+    Some(format!("{}{}{}{}{}", before, open, selected, close, after))
+}
+
+// Current selection (UTF-16 offsets) in the content input of component `id`
+fn content_selection(id: usize) -> Option<(usize, usize)> {
+    #[cfg(target_arch = "wasm32")]
+    {
+        use wasm_bindgen::JsCast;
+        let input = web_sys::window()?
+            .document()?
+            .get_element_by_id(&format!("content-input-{}", id))?
+            .dyn_into::<web_sys::HtmlInputElement>()
+            .ok()?;
+        let start = input.selection_start().ok()??;
+        let end = input.selection_end().ok()??;
+        Some((start as usize, end as usize))
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        let _ = id;
+        None
+    }
+}
+
+// Apply `tag` to the selected text of component `id`'s content input
+pub fn format_selection(id: usize, tag: &str) {
+    let Some((start, end)) = content_selection(id) else { return };
+    let Some(content) = EDITOR_STATE.read().components.get(&id).and_then(|c| toggle_format(&c.content, start, end, tag)) else { return };
+
+    let mut state = EDITOR_STATE.write();
+    state.mark_changed();
+    if let Some(component) = state.components.get_mut(&id) {
+        component.content = content;
+        component.touch();
+    }
+    sync_instances(&mut state, id);
+}
+
+// Bold / italic / underline buttons for the content input
+#[component]
+pub fn FormatToolbar(component_id: usize) -> Element {
+    rsx! {
+        div { style: "display: flex; gap: 4px; margin-bottom: 6px;",
+            for (tag, label, style, title) in [
+                ("strong", "B", "font-weight: bold;", "Bold"),
+                ("em", "I", "font-style: italic;", "Italic"),
+                ("u", "U", "text-decoration: underline;", "Underline"),
+            ] {
+                button {
+                    style: "width: 28px; {style}",
+                    title: "{title} (select text in the field below first)",
+                    // keep the input's focus and selection
+                    onmousedown: move |e| e.prevent_default(),
+                    onclick: move |_| format_selection(component_id, tag),
+                    "{label}"
+                }
+            }
+        }
+    }
+}
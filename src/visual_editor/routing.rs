@@ -0,0 +1,72 @@
+use super::component::EditorState;
+
+// Gap kept between a bent arrow and the boxes it goes around
+const CLEARANCE: f64 = 8.0;
+// How far each routing attempt moves the bend from the straight line's midpoint, and how many tries per side
+const BEND_STEP: f64 = 30.0;
+const MAX_BEND_STEPS: usize = 10;
+
+// Whether the segment (x1, y1)-(x2, y2) passes through the rectangle, grown by CLEARANCE on every side
+// (Liang-Barsky clipping)
+fn segment_hits_rect(x1: f64, y1: f64, x2: f64, y2: f64, (rx, ry, rw, rh): (f64, f64, f64, f64)) -> bool {
+    let (min_x, min_y, max_x, max_y) = (rx - CLEARANCE, ry - CLEARANCE, rx + rw + CLEARANCE, ry + rh + CLEARANCE);
+    let (dx, dy) = (x2 - x1, y2 - y1);
+    let (mut t0, mut t1) = (0.0f64, 1.0f64);
+    for (p, q) in [(-dx, x1 - min_x), (dx, max_x - x1), (-dy, y1 - min_y), (dy, max_y - y1)] {
+        if p == 0.0 {
+            if q < 0.0 {
+                return false;
+            }
+        } else {
+            let t = q / p;
+            if p < 0.0 {
+                t0 = t0.max(t);
+            } else {
+                t1 = t1.min(t);
+            }
+            if t0 > t1 {
+                return false;
+            }
+        }
+    }
+    true
+}
+
+// Points of the arrow from `parent` to `child` between the given edge points: the straight line when no other
+// box is in the way, otherwise one bend pushed sideways from the midpoint (nearest clear offset first).
+// Falls back to the straight line when no bend within reach clears every box.
+pub fn route_arrow(state: &EditorState, parent: usize, child: usize, start: (f64, f64), end: (f64, f64)) -> Vec<(f64, f64)> {
+    let obstacles = state.components.values()
+        .filter(|c| c.id != parent && c.id != child)
+        .map(|c| (c.x, c.y, c.width, c.height))
+        .collect::<Vec<_>>();
+    let clear = |a: (f64, f64), b: (f64, f64)| !obstacles.iter().any(|&rect| segment_hits_rect(a.0, a.1, b.0, b.1, rect));
+
+    if clear(start, end) {
+        return vec![start, end];
+    }
+
+    let (dx, dy) = (end.0 - start.0, end.1 - start.1);
+    let length = dx.hypot(dy);
+    if length == 0.0 {
+        return vec![start, end];
+    }
+    // unit normal to the straight line
+    let (nx, ny) = (-dy / length, dx / length);
+    let (mx, my) = ((start.0 + end.0) / 2.0, (start.1 + end.1) / 2.0);
+    for step in 1..=MAX_BEND_STEPS {
+        for side in [1.0, -1.0] {
+            let offset = side * step as f64 * BEND_STEP;
+            let bend = (mx + nx * offset, my + ny * offset);
+            if clear(start, bend) && clear(bend, end) {
+                return vec![start, bend, end];
+            }
+        }
+    }
+    vec![start, end]
+}
+
+// SVG `points` attribute for a polyline
+pub fn svg_points(points: &[(f64, f64)]) -> String {
+    points.iter().map(|(x, y)| format!("{},{}", x, y)).collect::<Vec<_>>().join(" ")
+}
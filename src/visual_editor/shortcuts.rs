@@ -0,0 +1,147 @@
+use dioxus::prelude::*;
+use super::component::{select_next, select_parent, set_mode, EditorMode, WindowListener, EDITOR_STATE};
+#[cfg(target_arch = "wasm32")]
+use super::component::CANVAS_ID;
+use super::connections::{move_among_siblings, move_into_sibling, move_out_of_parent};
+use super::layers::{cancel_pending_delete, confirm_pending_delete, request_keyboard_delete};
+use super::snippets::{copy_selected, cut_selected, duplicate_selected, paste_clipboard};
+use super::viewport::center_on;
+
+// Apply a keyboard shortcut; returns true when the key was handled (so the browser default is suppressed).
+// Only the web build has a key listener to call it; elsewhere it and the actions only it reaches are unused.
+#[cfg_attr(not(target_arch = "wasm32"), allow(dead_code))]
+pub fn handle_shortcut(key: &str, ctrl: bool, alt: bool, shift: bool) -> bool {
+    // while a delete confirmation is open, only its keys do anything
+    if EDITOR_STATE.read().pending_delete.is_some() {
+        return match key.to_ascii_lowercase().as_str() {
+            "enter" => { confirm_pending_delete(); true }
+            "escape" => { cancel_pending_delete(); true }
+            _ => false,
+        };
+    }
+
+    match (key.to_ascii_lowercase().as_str(), ctrl) {
+        ("e", true) => {
+            set_mode(EditorMode::Editor);
+            true
+        }
+        ("p", true) => {
+            set_mode(EditorMode::Preview);
+            true
+        }
+        // single-key toggle between Editor and Preview
+        ("m", true) => {
+            let mode = if EDITOR_STATE.read().mode == EditorMode::Editor { EditorMode::Preview } else { EditorMode::Editor };
+            set_mode(mode);
+            true
+        }
+        // F frames the selected component on the editor canvas
+        ("f", false) => {
+            let selected = { let s = EDITOR_STATE.read(); s.selected_id.filter(|_| s.mode == EditorMode::Editor) };
+            match selected {
+                Some(id) => { center_on(id); true }
+                None => false,
+            }
+        }
+        // Esc puts down an armed eyedropper, otherwise walks up the tree (unless a connection is being drawn)
+        ("escape", false) if EDITOR_STATE.read().eyedropper_active.is_some() => {
+            EDITOR_STATE.write().eyedropper_active = None;
+            true
+        }
+        ("escape", false) => {
+            let navigating = { let s = EDITOR_STATE.read(); s.mode == EditorMode::Editor && s.connecting_from.is_none() };
+            navigating && select_parent()
+        }
+        // Tab / Shift+Tab step through the components in document order
+        ("tab", false) => {
+            let navigating = { let s = EDITOR_STATE.read(); s.mode == EditorMode::Editor && s.connecting_from.is_none() };
+            navigating && select_next(shift)
+        }
+        // Alt+Up/Down reorder the selected component within its container;
+        // Ctrl+Up moves it out to the grandparent, Ctrl+Down into a neighbouring sibling container
+        ("arrowup" | "arrowdown", _) if ctrl || alt => {
+            let selected = { let s = EDITOR_STATE.read(); s.selected_id.filter(|_| s.mode == EditorMode::Editor && s.connecting_from.is_none()) };
+            let Some(id) = selected else { return false };
+            let up = key.eq_ignore_ascii_case("arrowup");
+            let result = if !ctrl {
+                move_among_siblings(id, up)
+            } else if up {
+                move_out_of_parent(id)
+            } else {
+                move_into_sibling(id)
+            };
+            match result {
+                Ok(moved) => moved,
+                Err(err) => {
+                    EDITOR_STATE.write().connect_error = Some(err.to_string());
+                    true
+                }
+            }
+        }
+        // Ctrl+C / Ctrl+X / Ctrl+V copy, cut and paste the selected component with everything below it
+        ("c" | "x" | "v", true) => {
+            let editing = { let s = EDITOR_STATE.read(); s.mode == EditorMode::Editor && s.connecting_from.is_none() };
+            editing && match key.to_ascii_lowercase().as_str() {
+                "c" => copy_selected(),
+                "x" => cut_selected(),
+                _ => paste_clipboard().is_some(),
+            }
+        }
+        // Ctrl+D duplicates the selected component with everything below it
+        ("d", true) => {
+            let editing = { let s = EDITOR_STATE.read(); s.mode == EditorMode::Editor && s.connecting_from.is_none() };
+            editing && duplicate_selected().is_some()
+        }
+        // Delete/Backspace removes the selected connection or components, asking first unless that's switched off
+        ("delete" | "backspace", false) => {
+            let editing = { let s = EDITOR_STATE.read(); s.mode == EditorMode::Editor && s.connecting_from.is_none() };
+            editing && request_keyboard_delete()
+        }
+        _ => false,
+    }
+}
+
+// Window-level keydown listener for the shortcuts, attached until the returned guard is dropped;
+// shortcuts are ignored while the user is typing in a form field
+pub fn install_keyboard_shortcuts() -> WindowListener {
+    #[cfg(target_arch = "wasm32")]
+    {
+        use wasm_bindgen::JsCast;
+        WindowListener::new("keydown", |e| {
+            let Ok(e) = e.dyn_into::<web_sys::KeyboardEvent>() else { return };
+            if is_typing_target(&e) {
+                return;
+            }
+            // Tab cycles the selection only on the focused canvas; everywhere else it moves focus as usual
+            if e.key() == "Tab" && !is_canvas_target(&e) {
+                return;
+            }
+            if handle_shortcut(&e.key(), e.ctrl_key() || e.meta_key(), e.alt_key(), e.shift_key()) {
+                e.prevent_default();
+            }
+        })
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        WindowListener::default()
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn is_typing_target(e: &web_sys::KeyboardEvent) -> bool {
+    use wasm_bindgen::JsCast;
+    e.target()
+        .and_then(|t| t.dyn_into::<web_sys::Element>().ok())
+        .is_some_and(|el| {
+            matches!(el.tag_name().as_str(), "INPUT" | "TEXTAREA" | "SELECT") || el.has_attribute("contenteditable")
+        })
+}
+
+#[cfg(target_arch = "wasm32")]
+fn is_canvas_target(e: &web_sys::KeyboardEvent) -> bool {
+    use wasm_bindgen::JsCast;
+    e.target()
+        .and_then(|t| t.dyn_into::<web_sys::Element>().ok())
+        .is_some_and(|el| el.id() == CANVAS_ID)
+}
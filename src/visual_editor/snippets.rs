@@ -0,0 +1,324 @@
+use dioxus::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use super::component::{now_ms, round_coord, Component, EditorState, EDITOR_STATE};
+use super::connections::EdgeStyle;
+use super::anchors::unique_anchor;
+use super::history::push_history;
+use super::layers::delete_components;
+use super::viewport::{canvas_size, screen_to_canvas};
+
+// A saved component subtree; ids are the ones it had when saved and get rebased on insert
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Snippet {
+    pub root: usize,
+    pub components: Vec<Component>,
+    // arrow styles of connections inside the subtree, as (parent, child, style)
+    #[serde(default)]
+    pub edge_styles: Vec<(usize, usize, EdgeStyle)>,
+}
+
+// How far a duplicate is moved from the original on the canvas
+const DUPLICATE_OFFSET: f64 = 20.0;
+
+// Named snippets; saved and loaded with the project
+pub static SNIPPET_LIBRARY: GlobalSignal<HashMap<String, Snippet>> = Signal::global(HashMap::new);
+
+// Subtree copied or cut with Ctrl+C / Ctrl+X; editor-only (not the system clipboard) and not saved
+pub static CLIPBOARD: GlobalSignal<Option<Snippet>> = Signal::global(|| None);
+
+// Component `id` and everything below it, with the arrow styles between them; None if `id` doesn't exist
+pub fn collect_subtree(state: &EditorState, id: usize) -> Option<Snippet> {
+    if !state.components.contains_key(&id) {
+        return None;
+    }
+    let mut ids = vec![id];
+    let mut i = 0;
+    while i < ids.len() {
+        for &child in state.components[&ids[i]].children.iter() {
+            if state.components.contains_key(&child) && !ids.contains(&child) {
+                ids.push(child);
+            }
+        }
+        i += 1;
+    }
+    let edge_styles = state.edge_styles.iter()
+        .filter(|((parent, child), _)| ids.contains(parent) && ids.contains(child))
+        .map(|(&(parent, child), style)| (parent, child, style.clone()))
+        .collect();
+    Some(Snippet {
+        root: id,
+        components: ids.iter().map(|id| state.components[id].clone()).collect(),
+        edge_styles,
+    })
+}
+
+// Save component `id` and everything below it under `name`, replacing a snippet with the same name
+pub fn save_snippet(name: &str, id: usize) -> bool {
+    let name = name.trim();
+    if name.is_empty() {
+        return false;
+    }
+    let Some(snippet) = collect_subtree(&EDITOR_STATE.read(), id) else { return false };
+    SNIPPET_LIBRARY.write().insert(name.to_string(), snippet);
+    // the library is saved with the project
+    EDITOR_STATE.write().mark_changed();
+    true
+}
+
+pub fn delete_snippet(name: &str) {
+    if SNIPPET_LIBRARY.write().remove(name).is_some() {
+        EDITOR_STATE.write().mark_changed();
+    }
+}
+
+pub fn insert_snippet(name: &str) -> Option<usize> {
+    let snippet = SNIPPET_LIBRARY.read().get(name).cloned()?;
+    insert_subtree(snippet, None)
+}
+
+// Put the selected component and everything below it on the clipboard
+pub fn copy_selected() -> bool {
+    let state = EDITOR_STATE.read();
+    let Some(snippet) = state.selected_id.and_then(|id| collect_subtree(&state, id)) else { return false };
+    *CLIPBOARD.write() = Some(snippet);
+    true
+}
+
+// Copy the selected subtree, then delete it as one undoable step. Nothing happens when any part of it
+// is locked (the locked part would stay behind and come back a second time on paste).
+pub fn cut_selected() -> bool {
+    let Some(snippet) = ({
+        let state = EDITOR_STATE.read();
+        state.selected_id.and_then(|id| collect_subtree(&state, id))
+    }) else { return false };
+    if snippet.components.iter().any(|c| c.locked) {
+        return false;
+    }
+    let root = snippet.root;
+    *CLIPBOARD.write() = Some(snippet);
+    delete_components(&[root], true);
+    true
+}
+
+// Insert a copy of the clipboard as one undoable step, inside the selected container when it may hold it.
+// The clipboard keeps its contents, so pasting again adds another copy.
+pub fn paste_clipboard() -> Option<usize> {
+    let snippet = CLIPBOARD.read().clone()?;
+    let parent = EDITOR_STATE.read().selected_id;
+    insert_subtree(snippet, parent)
+}
+
+// Copy component `id` and everything below it as one undoable step, placed just after the original in
+// its container and offset on the canvas. The copies only reference each other (children, arrows,
+// masters and link targets inside the subtree are remapped), so no original gains a second parent.
+// Returns the copy's id.
+pub fn duplicate_component(id: usize) -> Option<usize> {
+    if !EDITOR_STATE.read().components.contains_key(&id) {
+        return None;
+    }
+    push_history();
+    duplicate_in(&mut EDITOR_STATE.write(), id)
+}
+
+// duplicate_component on `state`, without recording history
+fn duplicate_in(state: &mut EditorState, id: usize) -> Option<usize> {
+    let snippet = collect_subtree(state, id)?;
+    let parent = state.document().parent_of(id);
+    let copy = rebase_into(state, snippet, parent, (DUPLICATE_OFFSET, DUPLICATE_OFFSET))?;
+    if let Some(parent) = parent {
+        if let Some(index) = state.document().children_of(parent).iter().position(|&child| child == id) {
+            let _ = state.document_mut().move_to(copy, Some(parent), index + 1);
+        }
+    }
+    Some(copy)
+}
+
+pub fn duplicate_selected() -> Option<usize> {
+    let selected = EDITOR_STATE.read().selected_id?;
+    duplicate_component(selected)
+}
+
+// Insert a copy of a saved tree as one undoable step, moved so its root lands in the middle of the
+// visible canvas. With `parent`, the root also becomes that container's last child if the connection
+// is allowed. Returns the new root's id.
+pub fn insert_subtree(snippet: Snippet, parent: Option<usize>) -> Option<usize> {
+    let root = snippet.components.iter().find(|c| c.id == snippet.root)?;
+    let (width, height) = canvas_size();
+    let (cx, cy) = screen_to_canvas(width / 2.0, height / 2.0);
+    let offset = (cx - (root.x + root.width / 2.0), cy - (root.y + root.height / 2.0));
+    push_history();
+    rebase_into(&mut EDITOR_STATE.write(), snippet, parent, offset)
+}
+
+// Add a copy of `snippet` to `state`, moved by (dx, dy), without recording history: every id in the
+// snippet is rebased onto next_id and references between its components are rewritten through that mapping
+pub fn rebase_into(state: &mut EditorState, snippet: Snippet, parent: Option<usize>, (dx, dy): (f64, f64)) -> Option<usize> {
+    if !snippet.components.iter().any(|c| c.id == snippet.root) {
+        return None;
+    }
+
+    let new_ids = snippet.components.iter()
+        .enumerate()
+        .map(|(i, c)| (c.id, state.next_id + i))
+        .collect::<HashMap<_, _>>();
+    state.next_id += snippet.components.len();
+
+    let now = now_ms();
+    let precision = state.coord_precision;
+    for mut comp in snippet.components {
+        comp.id = new_ids[&comp.id];
+        comp.children = comp.children.iter().filter_map(|child| new_ids.get(child).copied()).collect();
+        // instances follow a master copied along with them, or the original master if it still exists
+        comp.master_id = comp.master_id
+            .and_then(|master| new_ids.get(&master).copied().or(state.components.contains_key(&master).then_some(master)));
+        // links likewise follow a target copied along with them
+        comp.link_target = comp.link_target
+            .and_then(|target| new_ids.get(&target).copied().or(state.components.contains_key(&target).then_some(target)));
+        // anchors have to stay unique in the page, so a copy of one in use gets a numbered variant
+        comp.anchor_id = comp.anchor_id.map(|anchor| unique_anchor(state, &anchor));
+        comp.x = round_coord(comp.x + dx, precision);
+        comp.y = round_coord(comp.y + dy, precision);
+        comp.created_at = now;
+        comp.updated_at = now;
+        state.components.insert(comp.id, comp);
+    }
+    for (parent, child, style) in snippet.edge_styles {
+        if let (Some(&parent), Some(&child)) = (new_ids.get(&parent), new_ids.get(&child)) {
+            state.edge_styles.insert((parent, child), style);
+        }
+    }
+    let root_id = new_ids[&snippet.root];
+    if let Some(parent) = parent {
+        // a container that may not hold the root leaves it at the top level
+        let _ = state.document_mut().connect(parent, root_id);
+    }
+    state.select(Some(root_id));
+    Some(root_id)
+}
+
+#[component]
+pub fn SnippetPanel() -> Element {
+    let mut name = use_signal(String::new);
+    let selected_id = EDITOR_STATE.read().selected_id;
+    let mut names = SNIPPET_LIBRARY.read().keys().cloned().collect::<Vec<_>>();
+    names.sort();
+
+    rsx! {
+        div { style: "display: flex; flex-direction: column; gap: 6px; font-size: 12px;",
+            div { style: "display: flex; gap: 4px;",
+                input {
+                    r#type: "text",
+                    style: "flex: 1; min-width: 0;",
+                    placeholder: "Snippet name",
+                    value: "{name}",
+                    oninput: move |e| name.set(e.value()),
+                }
+                button {
+                    disabled: selected_id.is_none() || name().trim().is_empty(),
+                    title: "Save the selected component and its children",
+                    onclick: move |_| {
+                        if let Some(id) = selected_id {
+                            if save_snippet(&name(), id) {
+                                name.set(String::new());
+                            }
+                        }
+                    },
+                    "Save as snippet"
+                }
+            }
+            if names.is_empty() {
+                p { style: "color: #666; margin: 0;", "No snippets saved" }
+            }
+            for snippet_name in names {
+                div {
+                    key: "{snippet_name}",
+                    style: "display: flex; align-items: center; gap: 4px;",
+                    span { style: "flex: 1;", "{snippet_name}" }
+                    button {
+                        onclick: {
+                            let snippet_name = snippet_name.clone();
+                            move |_| { insert_snippet(&snippet_name); }
+                        },
+                        "Insert"
+                    }
+                    button {
+                        title: "Delete snippet",
+                        onclick: {
+                            let snippet_name = snippet_name.clone();
+                            move |_| delete_snippet(&snippet_name)
+                        },
+                        "×"
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::visual_editor::component::ComponentType;
+
+    // Container #0 > Container #1 > Heading #2
+    fn two_level_tree() -> EditorState {
+        let mut state = EditorState::default();
+        let mut doc = state.document_mut();
+        let outer = doc.add(Component::new(0, ComponentType::Container, 0.0, 0.0));
+        let inner = doc.add(Component::new(0, ComponentType::Container, 0.0, 100.0));
+        let heading = doc.add(Component::new(0, ComponentType::Heading, 0.0, 200.0));
+        doc.connect(outer, inner).unwrap();
+        doc.connect(inner, heading).unwrap();
+        state
+    }
+
+    fn parents(state: &EditorState, id: usize) -> usize {
+        state.components.values().filter(|c| c.children.contains(&id)).count()
+    }
+
+    #[test]
+    fn duplicate_shares_no_children_with_the_original() {
+        let mut state = two_level_tree();
+        let copy = duplicate_in(&mut state, 0).unwrap();
+
+        let originals = [0, 1, 2];
+        let copies = collect_subtree(&state, copy).unwrap().components.iter().map(|c| c.id).collect::<Vec<_>>();
+        assert_eq!(copies.len(), 3);
+        for id in &copies {
+            assert!(!originals.contains(id));
+            assert!(state.components[id].children.iter().all(|child| !originals.contains(child)));
+        }
+        assert_eq!(state.components[&0].children, vec![1]);
+        assert_eq!(state.components[&1].children, vec![2]);
+        assert_eq!(parents(&state, 1), 1);
+        assert_eq!(parents(&state, 2), 1);
+    }
+
+    #[test]
+    fn duplicate_goes_right_after_the_original() {
+        let mut state = two_level_tree();
+        let sibling = state.document_mut().add(Component::new(0, ComponentType::Heading, 0.0, 0.0));
+        state.document_mut().connect(0, sibling).unwrap();
+
+        let copy = duplicate_in(&mut state, 1).unwrap();
+        assert_eq!(state.components[&0].children, vec![1, copy, sibling]);
+        assert_eq!(parents(&state, copy), 1);
+    }
+
+    #[test]
+    fn duplicate_gets_unique_anchors_and_follows_its_own_links() {
+        let mut state = two_level_tree();
+        let link = state.document_mut().add(Component::new(0, ComponentType::Link, 0.0, 0.0));
+        state.document_mut().connect(1, link).unwrap();
+        state.components.get_mut(&2).unwrap().anchor_id = Some("pricing".to_string());
+        state.components.get_mut(&link).unwrap().link_target = Some(2);
+
+        let copy = duplicate_in(&mut state, 1).unwrap();
+        let copied = &state.components[&copy].children;
+        let (heading, link) = (copied[0], copied[1]);
+        assert_eq!(state.components[&heading].anchor_id.as_deref(), Some("pricing-2"));
+        assert_eq!(state.components[&link].link_target, Some(heading));
+        assert_eq!(state.components[&2].anchor_id.as_deref(), Some("pricing"));
+    }
+}
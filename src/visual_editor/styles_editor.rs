@@ -1,38 +1,176 @@
 use dioxus::prelude::*;
 use std::collections::HashMap;
-use super::component::EDITOR_STATE;
+use super::component::{ComponentType, EditorState, EDITOR_STATE};
+use super::contrast::is_color;
+use super::history::push_history;
+use super::symbols::sync_instances;
+
+// Pseudo component id under which StyleInput edits the page styles (preview wrapper / exported <body>)
+pub const PAGE_STYLE_ID: usize = usize::MAX;
 
 // Buffer of unsaved style edits per component (ordered)
 pub static STYLE_EDIT_BUFFER: GlobalSignal<HashMap<usize, Vec<(String, String)>>> = Signal::global(HashMap::new);
 
+// Styles of the most recently styled component of each type, keyed by type label; new components
+// start from these when `sticky_styles` is on
+pub static LAST_STYLES: GlobalSignal<HashMap<String, HashMap<String, String>>> = Signal::global(HashMap::new);
+
+// Colors recently saved into component styles, most recent first, offered as swatches in the editor
+pub static RECENT_COLORS: GlobalSignal<Vec<String>> = Signal::global(Vec::new);
+const MAX_RECENT_COLORS: usize = 12;
+
+// Record component `id`'s styles as the latest for its type and the colors that changed from `previous`
+// (its styles before the edit) as recently used; call after every style edit
+pub fn remember_styles(state: &EditorState, id: usize, previous: &HashMap<String, String>) {
+    if let Some(component) = state.components.get(&id) {
+        LAST_STYLES.write().insert(component.component_type.label().to_string(), component.styles.clone());
+        let mut colors = changed_colors(previous, &component.styles);
+        if !colors.is_empty() {
+            let mut recent = RECENT_COLORS.write();
+            recent.retain(|c| !colors.iter().any(|new| new.eq_ignore_ascii_case(c)));
+            colors.append(&mut recent);
+            colors.truncate(MAX_RECENT_COLORS);
+            *recent = colors;
+        }
+    }
+}
+
+// Valid color values in `styles` that differ from `previous`, without duplicates; re-saving an untouched
+// color doesn't count, so it doesn't jump to the front of the recent colors
+fn changed_colors(previous: &HashMap<String, String>, styles: &HashMap<String, String>) -> Vec<String> {
+    let mut colors: Vec<String> = Vec::new();
+    for property in COLOR_PROPERTIES {
+        let Some(value) = styles.get(*property).map(|v| v.trim()) else { continue };
+        let unchanged = previous.get(*property).is_some_and(|old| old.trim().eq_ignore_ascii_case(value));
+        if unchanged || !is_color(value) {
+            continue;
+        }
+        if !colors.iter().any(|c| c.eq_ignore_ascii_case(value)) {
+            colors.push(value.to_string());
+        }
+    }
+    colors
+}
+
+// Properties holding a color, which get an eyedropper button in the editor
+const COLOR_PROPERTIES: &[&str] = &["color", "background", "background-color", "border-color"];
+
+fn is_color_property(key: &str) -> bool {
+    COLOR_PROPERTIES.contains(&key.trim())
+}
+
+// Arm the eyedropper for row `row` of component `component_id`'s style edits; pressing it again disarms it
+fn toggle_eyedropper(component_id: usize, row: usize) {
+    let mut state = EDITOR_STATE.write();
+    state.eyedropper_active = if state.eyedropper_active == Some((component_id, row)) { None } else { Some((component_id, row)) };
+}
+
+// With the eyedropper armed, copy component `source_id`'s color into the waiting style row: its `color` for a
+// `color` row, its background for the others. Ends the eyedropper either way; returns false when it wasn't armed.
+pub fn pick_color(source_id: usize) -> bool {
+    let picked = {
+        let state = EDITOR_STATE.read();
+        let Some((component_id, row)) = state.eyedropper_active else { return false };
+        let key = STYLE_EDIT_BUFFER.read().get(&component_id).and_then(|pairs| pairs.get(row)).map(|(k, _)| k.trim().to_string());
+        let sources: &[&str] = if key.as_deref() == Some("color") { &["color"] } else { &["background-color", "background"] };
+        let value = state.components.get(&source_id)
+            .and_then(|c| sources.iter().find_map(|s| c.styles.get(*s)))
+            .cloned();
+        (component_id, row, value)
+    };
+    EDITOR_STATE.write().eyedropper_active = None;
+    if let (component_id, row, Some(value)) = picked {
+        if let Some(pair) = STYLE_EDIT_BUFFER.write().get_mut(&component_id).and_then(|pairs| pairs.get_mut(row)) {
+            pair.1 = value;
+        }
+    }
+    true
+}
+
+fn saved_styles(state: &EditorState, component_id: usize) -> Option<&HashMap<String, String>> {
+    if component_id == PAGE_STYLE_ID {
+        Some(&state.page_styles)
+    } else {
+        state.components.get(&component_id).map(|c| &c.styles)
+    }
+}
+
+const COMMON_PROPERTIES: &[&str] = &[
+    "background", "background-color", "border", "border-radius", "box-shadow", "margin", "padding",
+    "width", "height", "max-width", "opacity",
+];
+const LAYOUT_PROPERTIES: &[&str] = &[
+    "display", "flex-direction", "flex-wrap", "justify-content", "align-items", "gap",
+    "grid-template-columns", "grid-template-rows",
+];
+const TEXT_PROPERTIES: &[&str] = &[
+    "color", "font-family", "font-size", "font-weight", "font-style", "line-height", "letter-spacing",
+    "text-align", "text-decoration", "text-transform",
+];
+
+// Style properties offered in the key input's suggestions: layout for containers, typography for text,
+// both for the page itself; everything gets the common box properties
+fn suggested_properties(state: &EditorState, component_id: usize) -> Vec<&'static str> {
+    let (layout, text) = if component_id == PAGE_STYLE_ID {
+        (true, true)
+    } else {
+        match state.components.get(&component_id).map(|c| &c.component_type) {
+            Some(ComponentType::Container | ComponentType::Form | ComponentType::Spacer) => (true, false),
+            Some(ComponentType::Heading | ComponentType::Paragraph | ComponentType::Quote | ComponentType::Input | ComponentType::Link) => (false, true),
+            _ => (false, false),
+        }
+    };
+
+    let mut props = COMMON_PROPERTIES.to_vec();
+    if layout {
+        props.extend_from_slice(LAYOUT_PROPERTIES);
+    }
+    if text {
+        props.extend_from_slice(TEXT_PROPERTIES);
+    }
+    props.sort();
+    props
+}
+
 #[component]
 pub fn StyleInput(component_id: usize) -> Element {
     let state = EDITOR_STATE.read();
-    let component = state.components.get(&component_id);
+    let styles = saved_styles(&state, component_id);
 
-    if component.is_none() {
+    if styles.is_none() {
         return rsx!(div { "Component not found" });
     }
-    let component = component.unwrap();
+    let styles = styles.unwrap();
 
     // Initialize buffer for this component if not present
     {
-        let mut buf = STYLE_EDIT_BUFFER.write();
-        if !buf.contains_key(&component_id) {
-            buf.insert(component_id, component.styles.iter().map(|(k, v)| (k.clone(), v.clone())).collect::<Vec<_>>());
-        }
+        STYLE_EDIT_BUFFER.write()
+            .entry(component_id)
+            .or_insert_with(|| styles.iter().map(|(k, v)| (k.clone(), v.clone())).collect::<Vec<_>>());
     }
 
     // Read a snapshot for rendering
     let pairs_snapshot = { let buf = STYLE_EDIT_BUFFER.read(); buf.get(&component_id).cloned().unwrap_or_default() };
+    let suggestions = suggested_properties(&state, component_id);
+    let datalist_id = format!("style-properties-{}", component_id);
+    let eyedropper = state.eyedropper_active;
+    let recent_colors = RECENT_COLORS.read().clone();
 
     rsx! {
         div { 
             class: "styles-editor",
+            datalist { id: "{datalist_id}",
+                for prop in suggestions {
+                    option { value: prop }
+                }
+            }
             for (i, (key, value)) in pairs_snapshot.iter().enumerate() {
                 div {
                     input {
                         value: "{key}",
+                        list: "{datalist_id}",
+                        aria_label: "Style property",
+                        onkeydown: move |e| style_edit_key(e, component_id),
                         oninput: move |e| {
                             let mut buf = STYLE_EDIT_BUFFER.write();
                             if let Some(vec) = buf.get_mut(&component_id) {
@@ -42,6 +180,8 @@ pub fn StyleInput(component_id: usize) -> Element {
                     }
                     input {
                         value: "{value}",
+                        aria_label: "Value of {key}",
+                        onkeydown: move |e| style_edit_key(e, component_id),
                         oninput: move |e| {
                             let mut buf = STYLE_EDIT_BUFFER.write();
                             if let Some(vec) = buf.get_mut(&component_id) {
@@ -49,12 +189,38 @@ pub fn StyleInput(component_id: usize) -> Element {
                             }
                         }
                     }
+                    if is_color_property(key) {
+                        button {
+                            style: if eyedropper == Some((component_id, i)) { "background: var(--color-primary);" } else { "" },
+                            title: "Pick the {key} from a component on the canvas (Esc cancels)",
+                            aria_label: "Pick {key} from a component",
+                            aria_pressed: eyedropper == Some((component_id, i)),
+                            onclick: move |_| toggle_eyedropper(component_id, i),
+                            "💧"
+                        }
+                        for color in recent_colors.iter().cloned() {
+                            button {
+                                style: "width: 16px; height: 16px; min-width: 0; padding: 0; border: 1px solid #999; background: {color}; vertical-align: middle;",
+                                title: "{color}",
+                                aria_label: "Use {color}",
+                                onclick: move |_| {
+                                    if let Some(pair) = STYLE_EDIT_BUFFER.write().get_mut(&component_id).and_then(|pairs| pairs.get_mut(i)) {
+                                        pair.1 = color.clone();
+                                    }
+                                },
+                            }
+                        }
+                    }
                     button {
+                        title: "Remove {key}",
+                        aria_label: "Remove {key}",
                         onclick: move |_| {
                             let mut buf = STYLE_EDIT_BUFFER.write();
                             if let Some(vec) = buf.get_mut(&component_id) {
                                 if i < vec.len() { vec.remove(i); }
                             }
+                            // the rows below shift up, so an armed eyedropper would fill the wrong one
+                            EDITOR_STATE.write().eyedropper_active = None;
                         },
                         "X"
                     }
@@ -78,48 +244,147 @@ pub fn StyleInput(component_id: usize) -> Element {
                 }
 
                 button {
-                    onclick: move |_| {
-                        // Save: write ordered pairs into the component's HashMap (duplicates keep last)
-                        let pairs = { let buf = STYLE_EDIT_BUFFER.read(); buf.get(&component_id).cloned().unwrap_or_default() };
-                        let mut map = HashMap::new();
-                        for (k, v) in pairs.iter() {
-                            if !k.is_empty() {
-                                map.insert(k.clone(), v.clone());
-                            }
-                        }
-                        let mut s = EDITOR_STATE.write();
-                        if let Some(comp) = s.components.get_mut(&component_id) {
-                            comp.styles = map;
-                        }
-                        // remove buffer entry so next open loads fresh
-                        STYLE_EDIT_BUFFER.write().remove(&component_id);
-                    },
+                    title: "Enter in a style field",
+                    onclick: move |_| save_style_edits(component_id),
                     "Save"
                 }
 
                 button {
-                    onclick: move |_| {
-                        // Cancel: reset local edits from current component styles
-                        let s = EDITOR_STATE.read();
-                        if let Some(comp) = s.components.get(&component_id) {
-                            let reset = comp.styles.iter().map(|(k,v)| (k.clone(), v.clone())).collect::<Vec<_>>();
-                            STYLE_EDIT_BUFFER.write().insert(component_id, reset);
-                        }
-                    },
+                    title: "Esc in a style field",
+                    onclick: move |_| cancel_style_edits(component_id),
                     "Cancel"
                 }
+
+                button {
+                    disabled: styles.is_empty() && pairs_snapshot.is_empty(),
+                    title: "Remove every style (undoable)",
+                    onclick: move |_| reset_styles(component_id),
+                    "Reset styles"
+                }
             }
         }
     }
 }
-fn update_style<A>(component_id: usize, property: A, value: String) where A: Into<String> {
+// Save: write the buffered ordered pairs into the component's HashMap (duplicates keep last)
+fn save_style_edits(component_id: usize) {
+    let pairs = { let buf = STYLE_EDIT_BUFFER.read(); buf.get(&component_id).cloned().unwrap_or_default() };
+    let mut map = HashMap::new();
+    for (k, v) in pairs.iter() {
+        if !k.is_empty() {
+            map.insert(k.clone(), v.clone());
+        }
+    }
+    push_history();
+    {
+        let mut s = EDITOR_STATE.write();
+        if component_id == PAGE_STYLE_ID {
+            s.page_styles = map;
+        } else if let Some(comp) = s.components.get_mut(&component_id) {
+            let previous = std::mem::replace(&mut comp.styles, map);
+            comp.touch();
+            remember_styles(&s, component_id, &previous);
+            sync_instances(&mut s, component_id);
+        }
+    }
+    // remove buffer entry so next open loads fresh
+    STYLE_EDIT_BUFFER.write().remove(&component_id);
+}
+
+// Cancel: reset local edits from the current saved styles
+fn cancel_style_edits(component_id: usize) {
+    let s = EDITOR_STATE.read();
+    if let Some(styles) = saved_styles(&s, component_id) {
+        let reset = styles.iter().map(|(k,v)| (k.clone(), v.clone())).collect::<Vec<_>>();
+        STYLE_EDIT_BUFFER.write().insert(component_id, reset);
+    }
+}
+
+// Enter saves and Esc cancels while typing in a style field, so the editor works without the mouse
+fn style_edit_key(e: KeyboardEvent, component_id: usize) {
+    match e.key() {
+        Key::Enter => save_style_edits(component_id),
+        Key::Escape => {
+            // don't also walk the selection up to the parent
+            e.stop_propagation();
+            cancel_style_edits(component_id);
+        }
+        _ => {}
+    }
+}
+
+// Clear all of a component's (or the page's) styles and unsaved edits as one undoable step
+pub fn reset_styles(component_id: usize) {
+    push_history();
+    {
+        let mut state = EDITOR_STATE.write();
+        state.mark_changed();
+        if component_id == PAGE_STYLE_ID {
+            state.page_styles.clear();
+        } else if let Some(component) = state.components.get_mut(&component_id) {
+            let previous = std::mem::take(&mut component.styles);
+            component.touch();
+            remember_styles(&state, component_id, &previous);
+            sync_instances(&mut state, component_id);
+        }
+    }
+    STYLE_EDIT_BUFFER.write().remove(&component_id);
+}
+
+// Set (or remove, when empty) a single style immediately, keeping any unsaved StyleInput edits in sync
+// so a later Save doesn't overwrite it. Used by the quick controls in the properties panel.
+pub fn update_style<A>(component_id: usize, property: A, value: String) where A: Into<String> {
     let property = property.into();
-    let mut state = EDITOR_STATE.write();
-    if let Some(component) = state.components.get_mut(&component_id) {
-        if value.is_empty() {
-            component.styles.remove(&property);
-        } else {
-            component.styles.insert(property, value);
+    {
+        let mut state = EDITOR_STATE.write();
+        state.mark_changed();
+        let previous = state.components.get(&component_id).map(|c| c.styles.clone()).unwrap_or_default();
+        if let Some(component) = state.components.get_mut(&component_id) {
+            if value.is_empty() {
+                component.styles.remove(&property);
+            } else {
+                component.styles.insert(property.clone(), value.clone());
+            }
+            component.touch();
+        }
+        remember_styles(&state, component_id, &previous);
+        sync_instances(&mut state, component_id);
+    }
+
+    let mut buf = STYLE_EDIT_BUFFER.write();
+    if let Some(vec) = buf.get_mut(&component_id) {
+        match vec.iter().position(|(k, _)| k == &property) {
+            Some(i) if value.is_empty() => { vec.remove(i); }
+            Some(i) => vec[i].1 = value,
+            None if !value.is_empty() => vec.push((property, value)),
+            None => {}
         }
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn styles(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+    }
+
+    #[test]
+    fn untouched_colors_are_not_recent() {
+        let previous = styles(&[("color", "#ff0000"), ("background", "blue")]);
+        let current = styles(&[("color", "#FF0000 "), ("background", "blue"), ("padding", "4px")]);
+        assert!(changed_colors(&previous, &current).is_empty());
+    }
+
+    #[test]
+    fn changed_and_new_colors_are_recent() {
+        let previous = styles(&[("color", "red")]);
+        let current = styles(&[("color", "green"), ("border-color", "#123456")]);
+        assert_eq!(changed_colors(&previous, &current), vec!["green", "#123456"]);
+    }
+
+    #[test]
+    fn invalid_and_repeated_colors_are_skipped() {
+        let current = styles(&[("color", "#abc"), ("background", "#ABC"), ("background-color", "not-a-color")]);
+        assert_eq!(changed_colors(&HashMap::new(), &current), vec!["#abc"]);
+    }
+}
@@ -0,0 +1,110 @@
+use dioxus::prelude::*;
+use super::component::{now_ms, select_component, EditorState, EDITOR_STATE};
+use super::history::push_history;
+
+// Instances of `master`, lowest id first
+pub fn instances_of(state: &EditorState, master: usize) -> Vec<usize> {
+    let mut ids = state.components.values()
+        .filter(|c| c.master_id == Some(master))
+        .map(|c| c.id)
+        .collect::<Vec<_>>();
+    ids.sort();
+    ids
+}
+
+// Copy the master's content, quote source, input type, link target and styles onto its instances. Called after every content/style edit,
+// so changes made directly on an instance last until the master is edited again.
+pub fn sync_instances(state: &mut EditorState, master: usize) {
+    let Some(master) = state.components.get(&master).cloned() else { return };
+    for comp in state.components.values_mut().filter(|c| c.master_id == Some(master.id)) {
+        if comp.content != master.content || comp.cite != master.cite || comp.input_type != master.input_type
+            || comp.link_target != master.link_target || comp.styles != master.styles
+        {
+            comp.content = master.content.clone();
+            comp.cite = master.cite.clone();
+            comp.input_type = master.input_type.clone();
+            comp.link_target = master.link_target;
+            comp.styles = master.styles.clone();
+            comp.touch();
+        }
+    }
+}
+
+// Add an instance linked to `id` (or to `id`'s master, so instances never chain) next to it, as one
+// undoable step, and select it. Instances copy content and styles only; children aren't shared.
+pub fn create_instance(id: usize) -> Option<usize> {
+    let master = {
+        let state = EDITOR_STATE.read();
+        let comp = state.components.get(&id)?;
+        let master_id = comp.master_id.unwrap_or(id);
+        state.components.get(&master_id)?.clone()
+    };
+
+    push_history();
+    let new_id = {
+        let mut state = EDITOR_STATE.write();
+        let now = now_ms();
+        // document_mut().add assigns the id and leaves the children out
+        let mut instance = master.clone();
+        instance.x = master.x + 20.0;
+        instance.y = master.y + 20.0;
+        instance.created_at = now;
+        instance.updated_at = now;
+        instance.name = None;
+        instance.note = None;
+        // an anchor belongs to one component; the instance would only duplicate it
+        instance.anchor_id = None;
+        instance.locked = false;
+        instance.master_id = Some(master.id);
+        state.document_mut().add(instance)
+    };
+    select_component(new_id);
+    Some(new_id)
+}
+
+// Unlink an instance from its master as one undoable step; it keeps its current content and styles
+pub fn detach_instance(id: usize) {
+    if EDITOR_STATE.read().components.get(&id).is_none_or(|c| c.master_id.is_none()) {
+        return;
+    }
+    push_history();
+    let mut state = EDITOR_STATE.write();
+    if let Some(comp) = state.components.get_mut(&id) {
+        comp.master_id = None;
+        comp.touch();
+    }
+}
+
+// Symbol controls for the properties panel
+#[component]
+pub fn SymbolPanel(component_id: usize) -> Element {
+    let state = EDITOR_STATE.read();
+    let master_id = state.components.get(&component_id).and_then(|c| c.master_id);
+    let instance_count = instances_of(&state, component_id).len();
+
+    rsx! {
+        div { style: "display: flex; flex-direction: column; gap: 6px; padding-inline: 12px; font-size: 12px;",
+            if let Some(master_id) = master_id {
+                p { style: "margin: 0; color: #666;",
+                    "Instance of "
+                    span {
+                        style: "color: #2196F3; cursor: pointer; text-decoration: underline;",
+                        onclick: move |_| select_component(master_id),
+                        "#{master_id}"
+                    }
+                    ". Edits to the master replace this component's content and styles."
+                }
+                button { onclick: move |_| detach_instance(component_id), "Detach instance" }
+            } else {
+                if instance_count > 0 {
+                    p { style: "margin: 0; color: #666;", "Master of {instance_count} instance(s)" }
+                }
+                button {
+                    title: "Add a copy that follows this component's content and styles",
+                    onclick: move |_| { create_instance(component_id); },
+                    "Create symbol"
+                }
+            }
+        }
+    }
+}
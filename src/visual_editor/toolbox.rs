@@ -0,0 +1,102 @@
+use dioxus::prelude::*;
+use std::collections::BTreeSet;
+
+// localStorage key holding the titles of collapsed toolbox sections (JSON array)
+const COLLAPSED_KEY: &str = "cms.toolbox.collapsed";
+
+pub static COLLAPSED_SECTIONS: GlobalSignal<BTreeSet<String>> = Signal::global(load_collapsed);
+
+// localStorage key holding the UI density ("compact"; anything else is comfortable)
+const DENSITY_KEY: &str = "cms.density";
+
+// Tighter spacing across the editor, applied through the `density-compact` class on its root
+pub static COMPACT_UI: GlobalSignal<bool> = Signal::global(load_compact);
+
+fn load_compact() -> bool {
+    #[cfg(target_arch = "wasm32")]
+    {
+        let stored = web_sys::window()
+            .and_then(|w| w.local_storage().ok().flatten())
+            .and_then(|storage| storage.get_item(DENSITY_KEY).ok().flatten());
+        if let Some(density) = stored {
+            return density == "compact";
+        }
+    }
+    false
+}
+
+pub fn set_compact_ui(compact: bool) {
+    *COMPACT_UI.write() = compact;
+
+    #[cfg(target_arch = "wasm32")]
+    {
+        if let Some(storage) = web_sys::window().and_then(|w| w.local_storage().ok().flatten()) {
+            let _ = storage.set_item(DENSITY_KEY, if compact { "compact" } else { "comfortable" });
+        }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        // nothing to persist to outside the browser
+        let _ = DENSITY_KEY;
+    }
+}
+
+fn load_collapsed() -> BTreeSet<String> {
+    #[cfg(target_arch = "wasm32")]
+    {
+        let stored = web_sys::window()
+            .and_then(|w| w.local_storage().ok().flatten())
+            .and_then(|storage| storage.get_item(COLLAPSED_KEY).ok().flatten());
+        if let Some(json) = stored {
+            return serde_json::from_str(&json).unwrap_or_default();
+        }
+    }
+    BTreeSet::new()
+}
+
+fn save_collapsed(collapsed: &BTreeSet<String>) {
+    #[cfg(target_arch = "wasm32")]
+    {
+        if let Some(storage) = web_sys::window().and_then(|w| w.local_storage().ok().flatten()) {
+            let _ = storage.set_item(COLLAPSED_KEY, &serde_json::to_string(collapsed).unwrap_or_default());
+        }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        // nothing to persist to outside the browser
+        let _ = (collapsed, COLLAPSED_KEY);
+    }
+}
+
+pub fn toggle_section(title: &str) {
+    let mut collapsed = COLLAPSED_SECTIONS.write();
+    if !collapsed.remove(title) {
+        collapsed.insert(title.to_string());
+    }
+    save_collapsed(&collapsed);
+}
+
+// A titled sidebar section that collapses when its header is clicked; open/closed state survives reloads
+#[component]
+pub fn ToolboxSection(title: String, children: Element) -> Element {
+    let open = !COLLAPSED_SECTIONS.read().contains(&title);
+    let arrow = if open { "▾" } else { "▸" };
+
+    rsx! {
+        div { style: "margin-top: 24px;",
+            h3 {
+                style: "margin: 0 0 8px 0; font-size: 14px; cursor: pointer; user-select: none;",
+                onclick: {
+                    let title = title.clone();
+                    move |_| toggle_section(&title)
+                },
+                "{arrow} {title}"
+            }
+            if open {
+                {children}
+            }
+        }
+    }
+}
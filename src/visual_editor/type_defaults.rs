@@ -0,0 +1,167 @@
+use dioxus::prelude::*;
+use std::collections::HashMap;
+use super::component::{ComponentType, EDITOR_STATE};
+use super::registry::registered_component_types;
+
+// localStorage key holding the per-type default styles (JSON object keyed by type label)
+const TYPE_DEFAULTS_KEY: &str = "cms.type_defaults";
+
+// Styles new components of each type start with, keyed by type label; an editor setting, not part of the project
+pub static TYPE_DEFAULTS: GlobalSignal<HashMap<String, HashMap<String, String>>> = Signal::global(load_type_defaults);
+
+fn load_type_defaults() -> HashMap<String, HashMap<String, String>> {
+    #[cfg(target_arch = "wasm32")]
+    {
+        let stored = web_sys::window()
+            .and_then(|w| w.local_storage().ok().flatten())
+            .and_then(|storage| storage.get_item(TYPE_DEFAULTS_KEY).ok().flatten());
+        if let Some(json) = stored {
+            return serde_json::from_str(&json).unwrap_or_default();
+        }
+    }
+    HashMap::new()
+}
+
+fn save_type_defaults(defaults: &HashMap<String, HashMap<String, String>>) {
+    #[cfg(target_arch = "wasm32")]
+    {
+        if let Some(storage) = web_sys::window().and_then(|w| w.local_storage().ok().flatten()) {
+            let _ = storage.set_item(TYPE_DEFAULTS_KEY, &serde_json::to_string(defaults).unwrap_or_default());
+        }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        // nothing to persist to outside the browser
+        let _ = (defaults, TYPE_DEFAULTS_KEY);
+    }
+}
+
+pub fn default_styles(component_type: &ComponentType) -> HashMap<String, String> {
+    TYPE_DEFAULTS.read().get(component_type.label()).cloned().unwrap_or_default()
+}
+
+// Replace the defaults of one type; an empty map removes them
+pub fn set_default_styles(label: &str, styles: HashMap<String, String>) {
+    let mut defaults = TYPE_DEFAULTS.write();
+    if styles.is_empty() {
+        defaults.remove(label);
+    } else {
+        defaults.insert(label.to_string(), styles);
+    }
+    save_type_defaults(&defaults);
+}
+
+// `property: value` declarations separated by `;` or new lines; entries without a colon are skipped.
+// Separators inside parentheses or quotes belong to the value, as in `url(data:image/png;base64,...)`.
+pub fn parse_declarations(text: &str) -> HashMap<String, String> {
+    split_declarations(text).into_iter()
+        .filter_map(|decl| decl.split_once(':'))
+        .map(|(k, v)| (k.trim().to_string(), v.trim().to_string()))
+        .filter(|(k, v)| !k.is_empty() && !v.is_empty())
+        .collect()
+}
+
+fn split_declarations(text: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let (mut depth, mut quote, mut start) = (0usize, None, 0);
+    for (i, c) in text.char_indices() {
+        match (quote, c) {
+            (Some(q), _) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '"' | '\'') => quote = Some(c),
+            (None, '(') => depth += 1,
+            (None, ')') => depth = depth.saturating_sub(1),
+            (None, ';' | '\n') if depth == 0 => {
+                parts.push(&text[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(&text[start..]);
+    parts
+}
+
+// One declaration per line, sorted by property
+fn format_declarations(styles: &HashMap<String, String>) -> String {
+    let mut lines = styles.iter().map(|(k, v)| format!("{}: {};", k, v)).collect::<Vec<_>>();
+    lines.sort();
+    lines.join("\n")
+}
+
+// "Type defaults" toolbox pane: pick a type and edit the styles its new components get
+#[component]
+pub fn TypeDefaultsPanel() -> Element {
+    let mut label = use_signal(|| ComponentType::Heading.label().to_string());
+    let mut types = ComponentType::BUILT_IN.to_vec();
+    types.extend(registered_component_types().into_iter().map(ComponentType::Custom));
+    let text = format_declarations(&TYPE_DEFAULTS.read().get(&label()).cloned().unwrap_or_default());
+    // the selected component's styles, when it has the chosen type
+    let selected_styles = {
+        let state = EDITOR_STATE.read();
+        state.selected_id
+            .and_then(|id| state.components.get(&id))
+            .filter(|c| c.component_type.label() == label())
+            .map(|c| c.styles.clone())
+    };
+
+    rsx! {
+        div { style: "display: flex; flex-direction: column; gap: 6px; font-size: 12px;",
+            select {
+                aria_label: "Component type",
+                value: "{label}",
+                onchange: move |e| label.set(e.value()),
+                for t in types {
+                    option { value: "{t.label()}", "{t.label()}" }
+                }
+            }
+            textarea {
+                rows: "5",
+                style: "font-family: monospace; font-size: 12px;",
+                placeholder: "font-size: 32px;\nmargin: 0 0 16px 0;",
+                aria_label: "Default styles for {label}",
+                value: "{text}",
+                onchange: move |e| set_default_styles(&label(), parse_declarations(&e.value())),
+            }
+            button {
+                disabled: selected_styles.is_none(),
+                title: "Use the selected component's styles as the defaults for its type",
+                onclick: move |_| {
+                    if let Some(styles) = selected_styles.clone() {
+                        set_default_styles(&label(), styles);
+                    }
+                },
+                "Use selected component's styles"
+            }
+            p { style: "margin: 0; color: #666;", "New components of this type start with these styles. Saved in this browser." }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn declarations_split_on_semicolons_and_new_lines() {
+        let styles = parse_declarations("color: red; margin: 0 0 16px 0\npadding:4px;;broken");
+        assert_eq!(styles.len(), 3);
+        assert_eq!(styles["color"], "red");
+        assert_eq!(styles["margin"], "0 0 16px 0");
+        assert_eq!(styles["padding"], "4px");
+    }
+
+    #[test]
+    fn separators_inside_parentheses_and_quotes_stay_in_the_value() {
+        let styles = parse_declarations("background: url(data:image/png;base64,iVBO=) no-repeat; font-family: \"A;B\", serif");
+        assert_eq!(styles["background"], "url(data:image/png;base64,iVBO=) no-repeat");
+        assert_eq!(styles["font-family"], "\"A;B\", serif");
+    }
+
+    #[test]
+    fn formatted_declarations_parse_back() {
+        let styles = parse_declarations("background-image: url('a;b.png'); color: #fff");
+        assert_eq!(parse_declarations(&format_declarations(&styles)), styles);
+    }
+}
@@ -0,0 +1,179 @@
+use dioxus::prelude::*;
+use super::component::EDITOR_STATE;
+#[cfg(target_arch = "wasm32")]
+use super::component::CANVAS_ID;
+
+pub const MIN_ZOOM: f64 = 0.1;
+pub const MAX_ZOOM: f64 = 4.0;
+const ZOOM_STEP: f64 = 1.25;
+// Space left around the components when fitting them to the screen
+const FIT_PADDING: f64 = 40.0;
+const MINIMAP_WIDTH: f64 = 160.0;
+const MINIMAP_HEIGHT: f64 = 110.0;
+
+// Size of the visible canvas area in screen pixels
+pub fn canvas_size() -> (f64, f64) {
+    #[cfg(target_arch = "wasm32")]
+    {
+        if let Some(elem) = web_sys::window()
+            .and_then(|w| w.document())
+            .and_then(|d| d.get_element_by_id(CANVAS_ID))
+        {
+            return (elem.client_width() as f64, elem.client_height() as f64);
+        }
+    }
+
+    // Non-web targets have no element to measure; assume a typical editor area
+    (1000.0, 700.0)
+}
+
+// Canvas-relative screen point -> canvas coordinates (the space component x/y live in)
+pub fn screen_to_canvas(x: f64, y: f64) -> (f64, f64) {
+    let state = EDITOR_STATE.read();
+    ((x - state.pan_x) / state.zoom, (y - state.pan_y) / state.zoom)
+}
+
+// Zoom to `zoom` keeping the canvas point under the screen point (cx, cy) in place
+pub fn zoom_around(zoom: f64, cx: f64, cy: f64) {
+    let mut state = EDITOR_STATE.write();
+    let zoom = zoom.clamp(MIN_ZOOM, MAX_ZOOM);
+    let (wx, wy) = ((cx - state.pan_x) / state.zoom, (cy - state.pan_y) / state.zoom);
+    state.pan_x = cx - wx * zoom;
+    state.pan_y = cy - wy * zoom;
+    state.zoom = zoom;
+}
+
+// Zoom in (positive steps) or out (negative) around the middle of the canvas
+pub fn zoom_by(steps: i32) {
+    let (width, height) = canvas_size();
+    let zoom = EDITOR_STATE.read().zoom * ZOOM_STEP.powi(steps);
+    zoom_around(zoom, width / 2.0, height / 2.0);
+}
+
+pub fn reset_view() {
+    let mut state = EDITOR_STATE.write();
+    state.zoom = 1.0;
+    state.pan_x = 0.0;
+    state.pan_y = 0.0;
+}
+
+// Set zoom/pan so every component's box is visible and centered, with some padding.
+// An empty document just resets the view.
+pub fn fit_to_screen() {
+    let bounds = {
+        let state = EDITOR_STATE.read();
+        state.components.values().fold(None, |acc: Option<(f64, f64, f64, f64)>, c| {
+            let (x0, y0, x1, y1) = acc.unwrap_or((c.x, c.y, c.x + c.width, c.y + c.height));
+            Some((x0.min(c.x), y0.min(c.y), x1.max(c.x + c.width), y1.max(c.y + c.height)))
+        })
+    };
+    let Some((min_x, min_y, max_x, max_y)) = bounds else {
+        reset_view();
+        return;
+    };
+
+    let (width, height) = canvas_size();
+    let (content_w, content_h) = (max_x - min_x, max_y - min_y);
+    let zoom = ((width - 2.0 * FIT_PADDING) / content_w)
+        .min((height - 2.0 * FIT_PADDING) / content_h)
+        .clamp(MIN_ZOOM, MAX_ZOOM);
+
+    let mut state = EDITOR_STATE.write();
+    state.zoom = zoom;
+    state.pan_x = (width - content_w * zoom) / 2.0 - min_x * zoom;
+    state.pan_y = (height - content_h * zoom) / 2.0 - min_y * zoom;
+}
+
+// Pan (keeping the zoom) so component `id` sits in the middle of the canvas
+pub fn center_on(id: usize) {
+    let Some((cx, cy)) = EDITOR_STATE.read().components.get(&id).map(|c| (c.x + c.width / 2.0, c.y + c.height / 2.0)) else { return };
+    pan_to(cx, cy);
+}
+
+// Center on component `id` unless its whole box is already visible
+pub fn scroll_into_view(id: usize) {
+    let (width, height) = canvas_size();
+    let visible = {
+        let state = EDITOR_STATE.read();
+        let Some(c) = state.components.get(&id) else { return };
+        let (left, top) = (c.x * state.zoom + state.pan_x, c.y * state.zoom + state.pan_y);
+        let (right, bottom) = (left + c.width * state.zoom, top + c.height * state.zoom);
+        left >= 0.0 && top >= 0.0 && right <= width && bottom <= height
+    };
+    if !visible {
+        center_on(id);
+    }
+}
+
+// Pan (keeping the zoom) so the canvas point (cx, cy) sits in the middle of the canvas
+pub fn pan_to(cx: f64, cy: f64) {
+    let (width, height) = canvas_size();
+
+    let mut state = EDITOR_STATE.write();
+    state.pan_x = width / 2.0 - cx * state.zoom;
+    state.pan_y = height / 2.0 - cy * state.zoom;
+}
+
+#[component]
+pub fn ViewportControls() -> Element {
+    let zoom_pct = (EDITOR_STATE.read().zoom * 100.0).round();
+
+    rsx! {
+        div { style: "display: flex; align-items: center; gap: 4px; margin-top: 8px; font-size: 12px;",
+            button { onclick: move |_| zoom_by(-1), "−" }
+            button { title: "Reset zoom", onclick: move |_| reset_view(), "{zoom_pct}%" }
+            button { onclick: move |_| zoom_by(1), "+" }
+            button { title: "Frame all components", onclick: move |_| fit_to_screen(), "Fit to screen" }
+        }
+    }
+}
+
+// Scaled-down overview of every component plus the visible area; clicking it pans the view there
+#[component]
+pub fn Minimap() -> Element {
+    let state = EDITOR_STATE.read();
+    if state.components.is_empty() {
+        return rsx! {};
+    }
+
+    // the visible area in canvas coordinates, so the indicator stays inside the map when panned away
+    let (width, height) = canvas_size();
+    let (view_x, view_y) = (-state.pan_x / state.zoom, -state.pan_y / state.zoom);
+    let (view_w, view_h) = (width / state.zoom, height / state.zoom);
+    let (min_x, min_y, max_x, max_y) = state.components.values().fold(
+        (view_x, view_y, view_x + view_w, view_y + view_h),
+        |(x0, y0, x1, y1), c| (x0.min(c.x), y0.min(c.y), x1.max(c.x + c.width), y1.max(c.y + c.height)),
+    );
+    let scale = (MINIMAP_WIDTH / (max_x - min_x)).min(MINIMAP_HEIGHT / (max_y - min_y));
+
+    let rects = state.components.values()
+        .map(|c| {
+            let color = if state.selected_ids.contains(&c.id) { "#2196F3" } else { "#9e9e9e" };
+            (c.id, (c.x - min_x) * scale, (c.y - min_y) * scale, (c.width * scale).max(2.0), (c.height * scale).max(2.0), color)
+        })
+        .collect::<Vec<_>>();
+    let (vx, vy, vw, vh) = ((view_x - min_x) * scale, (view_y - min_y) * scale, view_w * scale, view_h * scale);
+
+    rsx! {
+        div {
+            style: "position: absolute; right: 8px; bottom: 8px; z-index: 10; width: {MINIMAP_WIDTH}px; height: {MINIMAP_HEIGHT}px;
+                    background: rgba(255,255,255,0.85); border: 1px solid #ccc; border-radius: 4px; overflow: hidden; cursor: pointer;",
+            title: "Click to move the view",
+            onmousedown: move |e| e.stop_propagation(),
+            onclick: move |e| {
+                let point = e.element_coordinates();
+                pan_to(min_x + point.x / scale, min_y + point.y / scale);
+            },
+            for (id, x, y, w, h, color) in rects {
+                div {
+                    key: "{id}",
+                    style: "position: absolute; left: {x}px; top: {y}px; width: {w}px; height: {h}px; background: {color}; pointer-events: none;",
+                }
+            }
+            div {
+                style: "position: absolute; left: {vx}px; top: {vy}px; width: {vw}px; height: {vh}px;
+                        border: 1px solid #2196F3; background: rgba(33,150,243,0.1); pointer-events: none;",
+            }
+        }
+    }
+}